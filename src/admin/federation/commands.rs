@@ -5,6 +5,7 @@
 use ruma::{
 	events::room::message::RoomMessageEventContent, OwnedRoomId, RoomId, ServerName, UserId,
 };
+use service::sending::Destination;
 
 use crate::{admin_command, get_room_info};
 
@@ -133,3 +134,54 @@ pub(super) async fn remote_user_in_rooms(
 
 	Ok(RoomMessageEventContent::text_markdown(output))
 }
+
+#[admin_command]
+pub(super) async fn destination_stats(
+	&self,
+	server_name: Option<Box<ServerName>>,
+) -> Result<RoomMessageEventContent> {
+	let destinations = match server_name {
+		| Some(server_name) => self
+			.services
+			.federation
+			.metrics
+			.destination(&server_name)
+			.map(|stats| vec![(server_name.into(), stats)])
+			.unwrap_or_default(),
+		| None => self.services.federation.metrics.destinations(),
+	};
+
+	if destinations.is_empty() {
+		return Ok(RoomMessageEventContent::text_plain(
+			"No federation requests have been sent yet.",
+		));
+	}
+
+	let mut output = String::from(
+		"| Server Name | Requests | Errors | Mean Latency | Max Latency | Latency Histogram | \
+		 Backlog |\n| ----------- | --------:| ------:| ------------ | ----------- | \
+		 ----------------- | -------:|\n",
+	);
+
+	for (name, stats) in destinations {
+		let backlog = self
+			.services
+			.sending
+			.db
+			.queued_requests(&Destination::Federation(name.clone()))
+			.count()
+			.await;
+
+		let requests = stats.requests;
+		let errors = stats.errors;
+		let mean = stats.latency_mean();
+		let max = stats.latency_max;
+		let buckets = stats.latency_buckets;
+		writeln!(
+			output,
+			"| {name} | {requests} | {errors} | {mean:?} | {max:?} | {buckets:?} | {backlog} |"
+		)?;
+	}
+
+	Ok(RoomMessageEventContent::notice_markdown(output))
+}
@@ -39,4 +39,13 @@ pub(super) enum FederationCommand {
 	RemoteUserInRooms {
 		user_id: Box<UserId>,
 	},
+
+	/// - Show outgoing request statistics and the transaction backlog for
+	///   remote servers
+	///
+	/// Latency histogram buckets are bounded by 100ms, 250ms, 1s, 5s, 30s and
+	/// unbounded respectively.
+	DestinationStats {
+		server_name: Option<Box<ServerName>>,
+	},
 }
@@ -67,3 +67,23 @@ pub(super) async fn exists(&self, room_id: OwnedRoomId) -> Result<RoomMessageEve
 
 	Ok(RoomMessageEventContent::notice_markdown(format!("{result}")))
 }
+
+#[admin_command]
+pub(super) async fn reindex_search(
+	&self,
+	room_id: Option<OwnedRoomId>,
+) -> Result<RoomMessageEventContent> {
+	let Some(room_id) = room_id else {
+		let indexed = self.services.rooms.search.reindex_rooms().await?;
+
+		return Ok(RoomMessageEventContent::notice_markdown(format!(
+			"Reindexed {indexed} messages in all rooms."
+		)));
+	};
+
+	let indexed = self.services.rooms.search.reindex_room(&room_id).await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Reindexed {indexed} messages in {room_id}."
+	)))
+}
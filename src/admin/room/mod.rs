@@ -56,4 +56,11 @@ pub(super) enum RoomCommand {
 	Exists {
 		room_id: OwnedRoomId,
 	},
+
+	/// - Rebuild the message search index from stored timelines
+	///
+	/// Rebuilds every room when no room is given.
+	ReindexSearch {
+		room_id: Option<OwnedRoomId>,
+	},
 }
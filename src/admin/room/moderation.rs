@@ -74,6 +74,17 @@ pub(crate) enum RoomModerationCommand {
 		/// information
 		no_details: bool,
 	},
+
+	/// - List room and event reports made by local users, newest first
+	ListReports {
+		/// Only list reports for this room
+		room_id: Option<OwnedRoomId>,
+	},
+
+	/// - Dismiss a report, removing it from `list-reports`
+	DismissReport {
+		report_id: u64,
+	},
 }
 
 #[admin_command]
@@ -637,3 +648,59 @@ async fn list_banned_rooms(&self, no_details: bool) -> Result<RoomMessageEventCo
 
 	Ok(RoomMessageEventContent::notice_markdown(output_plain))
 }
+
+#[admin_command]
+async fn list_reports(&self, room_id: Option<OwnedRoomId>) -> Result<RoomMessageEventContent> {
+	let reports: Vec<_> = self
+		.services
+		.rooms
+		.reports
+		.reports()
+		.ready_filter(|(_, report)| {
+			room_id
+				.as_ref()
+				.is_none_or(|room_id| *room_id == report.room_id)
+		})
+		.map(|(report_id, report)| {
+			let event = report.event_id.map_or_else(
+				|| "room".to_owned(),
+				|event_id| report.room_id.matrix_to_event_uri(event_id).to_string(),
+			);
+
+			format!(
+				"{report_id}\t{}\t{}\t{event}\tScore: {}\tReason: {}",
+				report.reporter,
+				report.room_id,
+				report.score.unwrap_or_default(),
+				report.reason.as_deref().unwrap_or(""),
+			)
+		})
+		.collect()
+		.await;
+
+	if reports.is_empty() {
+		return Ok(RoomMessageEventContent::text_plain("No reports found."));
+	}
+
+	let output_plain = format!("Reports ({}):\n```\n{}\n```", reports.len(), reports.join("\n"));
+
+	Ok(RoomMessageEventContent::notice_markdown(output_plain))
+}
+
+#[admin_command]
+async fn dismiss_report(&self, report_id: u64) -> Result<RoomMessageEventContent> {
+	if self
+		.services
+		.rooms
+		.reports
+		.get_report(report_id)
+		.await
+		.is_err()
+	{
+		return Ok(RoomMessageEventContent::text_plain("Report does not exist."));
+	}
+
+	self.services.rooms.reports.remove_report(report_id);
+
+	Ok(RoomMessageEventContent::text_plain(format!("Dismissed report {report_id}.")))
+}
@@ -935,3 +935,99 @@ pub(super) async fn redact_event(
 
 	Ok(RoomMessageEventContent::text_plain(""))
 }
+
+#[admin_command]
+pub(super) async fn issue_registration_token(
+	&self,
+	token: Option<String>,
+	uses_allowed: Option<u64>,
+	expires_in: Option<String>,
+) -> Result<RoomMessageEventContent> {
+	let expiry_time = expires_in
+		.as_deref()
+		.map(utils::time::parse_duration)
+		.transpose()?
+		.map(|duration| {
+			let duration = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+			utils::millis_since_unix_epoch().saturating_add(duration)
+		});
+
+	let token = self
+		.services
+		.uiaa
+		.issue_registration_token(token, uses_allowed, expiry_time)
+		.await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Issued registration token `{token}`"
+	)))
+}
+
+#[admin_command]
+pub(super) async fn revoke_registration_token(
+	&self,
+	token: String,
+) -> Result<RoomMessageEventContent> {
+	self.services
+		.uiaa
+		.revoke_registration_token(&token)
+		.await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Revoked registration token `{token}`"
+	)))
+}
+
+#[admin_command]
+pub(super) async fn list_registration_tokens(&self) -> Result<RoomMessageEventContent> {
+	let tokens: Vec<_> = self
+		.services
+		.uiaa
+		.registration_tokens()
+		.map(|(token, info)| {
+			let uses_allowed = info
+				.uses_allowed
+				.map_or_else(|| "unlimited".to_owned(), |uses| uses.to_string());
+
+			let expiry_time = info
+				.expiry_time
+				.and_then(|expiry| i64::try_from(expiry / 1000).ok())
+				.map_or_else(|| "never".to_owned(), utils::time::rfc2822_from_seconds);
+
+			format!(
+				"{token} | uses: {}/{uses_allowed} | expires: {expiry_time} | valid: {}",
+				info.completed,
+				info.is_valid()
+			)
+		})
+		.collect()
+		.await;
+
+	let mut plain_msg = format!("Found {} registration token(s):\n```\n", tokens.len());
+	plain_msg += tokens.join("\n").as_str();
+	plain_msg += "\n```";
+
+	self.write_str(plain_msg.as_str()).await?;
+
+	Ok(RoomMessageEventContent::text_plain(""))
+}
+
+#[admin_command]
+pub(super) async fn send_server_notice(
+	&self,
+	user_id: String,
+	message: Vec<String>,
+) -> Result<RoomMessageEventContent> {
+	let user_id = parse_active_local_user_id(self.services, &user_id).await?;
+	let content = RoomMessageEventContent::notice_markdown(message.join(" "));
+
+	let event_id = self
+		.services
+		.server_notices
+		.send_notice(&user_id, content)
+		.await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Sent server notice {event_id} to {user_id}"
+	)))
+}
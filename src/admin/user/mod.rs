@@ -153,4 +153,38 @@ pub(super) enum UserCommand {
 		#[arg(long)]
 		yes_i_want_to_do_this: bool,
 	},
+
+	/// - Issue a new registration token
+	///
+	/// Registration tokens issued here are stored in the database in addition
+	/// to any configured with `registration_token`.
+	IssueRegistrationToken {
+		/// The token, if unspecified one is generated
+		token: Option<String>,
+
+		/// Number of registrations the token may be used for
+		#[arg(short, long)]
+		uses_allowed: Option<u64>,
+
+		/// How long the token is valid for, e.g. "7d" or "12h"
+		#[arg(short, long)]
+		expires_in: Option<String>,
+	},
+
+	/// - Revoke a registration token issued with `issue-registration-token`
+	RevokeRegistrationToken {
+		token: String,
+	},
+
+	/// - List all registration tokens issued with `issue-registration-token`
+	ListRegistrationTokens,
+
+	/// - Send a server notice to a local user
+	///
+	/// The notice is delivered in the user's server notices room, which is
+	/// created if needed. Requires `server_notices_user` to be configured.
+	SendServerNotice {
+		user_id: String,
+		message: Vec<String>,
+	},
 }
@@ -1,11 +1,14 @@
 use std::fmt::Write;
 
-use axum::extract::State;
+use axum::{extract::State, response::Html};
 use axum_client_ip::InsecureClientIp;
 use conduwuit::{
-	debug_info, error, info, is_equal_to, utils, utils::ReadyExt, warn, Error, PduBuilder, Result,
+	debug_info, err, error, info, is_equal_to, utils,
+	utils::{HtmlEscape, ReadyExt},
+	warn, Err, Error, PduBuilder, Result,
 };
 use futures::{FutureExt, StreamExt};
+use http::Uri;
 use register::RegistrationKind;
 use ruma::{
 	api::client::{
@@ -14,6 +17,7 @@
 			get_username_availability,
 			register::{self, LoginType},
 			request_3pid_management_token_via_email, request_3pid_management_token_via_msisdn,
+			request_password_change_token_via_email, request_registration_token_via_email,
 			whoami, ThirdPartyIdRemovalStatus,
 		},
 		error::ErrorKind,
@@ -28,6 +32,8 @@
 	},
 	push, OwnedRoomId, UserId,
 };
+use serde::Deserialize;
+use serde_json::{json, value::to_raw_value};
 use service::Services;
 
 use super::{join_room_by_id_helper, DEVICE_ID_LENGTH, SESSION_ID_LENGTH, TOKEN_LENGTH};
@@ -128,11 +134,11 @@ pub(crate) async fn register_route(
 	}
 
 	let is_guest = body.kind == RegistrationKind::Guest;
+	let registration_token_required = services.uiaa.registration_token_required().await;
 
 	if is_guest
 		&& (!services.globals.allow_guest_registration()
-			|| (services.globals.allow_registration()
-				&& services.globals.registration_token.is_some()))
+			|| (services.globals.allow_registration() && registration_token_required))
 	{
 		info!(
 			"Guest registration disabled / registration enabled with token configured, \
@@ -230,47 +236,75 @@ pub(crate) async fn register_route(
 	}
 
 	// UIAA
-	let mut uiaainfo;
-	let skip_auth = if services.globals.registration_token.is_some() {
+	let mut stages = Vec::new();
+	if registration_token_required {
 		// Registration token required
-		uiaainfo = UiaaInfo {
-			flows: vec![AuthFlow {
-				stages: vec![AuthType::RegistrationToken],
-			}],
-			completed: Vec::new(),
-			params: Box::default(),
-			session: None,
-			auth_error: None,
-		};
-		body.appservice_info.is_some()
-	} else {
+		stages.push(AuthType::RegistrationToken);
+	}
+
+	let recaptcha_site_key = services.uiaa.recaptcha_site_key();
+	if recaptcha_site_key.is_some() {
+		stages.push(AuthType::ReCaptcha);
+	}
+
+	let mut flows = Vec::new();
+	if services.email.required_for_registration() {
+		stages.push(AuthType::EmailIdentity);
+	} else if services.email.is_enabled() {
+		// Adding an email address is optional, but lets users reset their password
+		let mut email_stages = stages.clone();
+		email_stages.push(AuthType::EmailIdentity);
+		flows.push(AuthFlow { stages: email_stages });
+	}
+
+	let skip_auth = body.appservice_info.is_some() || (is_guest && !registration_token_required);
+	if stages.is_empty() {
 		// No registration token necessary, but clients must still go through the flow
-		uiaainfo = UiaaInfo {
-			flows: vec![AuthFlow { stages: vec![AuthType::Dummy] }],
-			completed: Vec::new(),
-			params: Box::default(),
-			session: None,
-			auth_error: None,
-		};
-		body.appservice_info.is_some() || is_guest
+		stages.push(AuthType::Dummy);
+	}
+
+	flows.insert(0, AuthFlow { stages });
+
+	let params = match recaptcha_site_key {
+		| Some(public_key) => to_raw_value(&json!({
+			"m.login.recaptcha": { "public_key": public_key },
+		}))?,
+		| None => Box::default(),
 	};
 
+	let mut uiaainfo = UiaaInfo {
+		flows,
+		completed: Vec::new(),
+		params,
+		session: None,
+		auth_error: None,
+	};
+
+	let mut email = None;
+	let mut registration_token = None;
 	if !skip_auth {
 		if let Some(auth) = &body.auth {
+			let anonymous = UserId::parse_with_server_name("", services.globals.server_name())
+				.expect("we know this is valid");
+
 			let (worked, uiaainfo) = services
 				.uiaa
-				.try_auth(
-					&UserId::parse_with_server_name("", services.globals.server_name())
-						.expect("we know this is valid"),
-					"".into(),
-					auth,
-					&uiaainfo,
-				)
+				.try_auth(&anonymous, "".into(), auth, &uiaainfo)
 				.await?;
 			if !worked {
 				return Err(Error::Uiaa(uiaainfo));
 			}
-		// Success!
+			// Success!
+
+			if let Some(session) = uiaainfo.session.as_deref() {
+				email = services
+					.uiaa
+					.take_validated_email(&anonymous, "".into(), session);
+
+				registration_token = services
+					.uiaa
+					.take_registration_token(&anonymous, "".into(), session);
+			}
 		} else if let Some(json) = body.json_body {
 			uiaainfo.session = Some(utils::random_string(SESSION_ID_LENGTH));
 			services.uiaa.create(
@@ -286,11 +320,40 @@ pub(crate) async fn register_route(
 		}
 	}
 
+	if let Some(email) = &email {
+		if services.email.user_for_email(email).await.is_ok() {
+			return Err!(Request(ThreepidInUse("Email address is already in use.")));
+		}
+	}
+
+	// The token stays locked until its use is counted, so concurrent
+	// registrations can't use it beyond its limit
+	let registration_token_lock = match &registration_token {
+		| Some(token) => Some(services.uiaa.lock_registration_token(token).await),
+		| None => None,
+	};
+
+	if let Some(token) = &registration_token {
+		if !services.uiaa.is_valid_registration_token(token).await {
+			return Err!(Request(Forbidden("Registration token is no longer valid.")));
+		}
+	}
+
 	let password = if is_guest { None } else { body.password.as_deref() };
 
 	// Create user
 	services.users.create(&user_id, password)?;
 
+	if let Some(token) = &registration_token {
+		services.uiaa.use_registration_token(token).await;
+	}
+
+	drop(registration_token_lock);
+
+	if let Some(email) = &email {
+		services.email.set_email(&user_id, email).await?;
+	}
+
 	// Default to pretty displayname
 	let mut displayname = user_id.localpart().to_owned();
 
@@ -347,12 +410,24 @@ pub(crate) async fn register_route(
 		.create_device(
 			&user_id,
 			&device_id,
-			&token,
+			Some(token.as_str()),
 			body.initial_device_display_name.clone(),
 			Some(client.to_string()),
 		)
 		.await?;
 
+	let (refresh_token, expires_in) = if body.refresh_token {
+		let refresh_token = utils::random_string(TOKEN_LENGTH);
+		let expires_in = services
+			.users
+			.set_refresh_token(&user_id, &device_id, &token, &refresh_token)
+			.await;
+
+		(Some(refresh_token), Some(expires_in))
+	} else {
+		(None, None)
+	};
+
 	debug_info!(%user_id, %device_id, "User account was created");
 
 	let device_display_name = body.initial_device_display_name.as_deref().unwrap_or("");
@@ -488,8 +563,8 @@ pub(crate) async fn register_route(
 		access_token: Some(token),
 		user_id,
 		device_id: Some(device_id),
-		refresh_token: None,
-		expires_in: None,
+		refresh_token,
+		expires_in,
 	})
 }
 
@@ -498,6 +573,8 @@ pub(crate) async fn register_route(
 /// Changes the password of this account.
 ///
 /// - Requires UIAA to verify user password
+/// - Users who aren't logged in can instead validate the email address of
+///   their account, if email verification is enabled
 /// - Changes the password of the sender user
 /// - The password hash is calculated using argon2 with 32 character salt, the
 ///   plain password is
@@ -516,12 +593,14 @@ pub(crate) async fn change_password_route(
 	InsecureClientIp(client): InsecureClientIp,
 	body: Ruma<change_password::v3::Request>,
 ) -> Result<change_password::v3::Response> {
-	// Authentication for this endpoint was made optional, but we need
-	// authentication currently
-	let sender_user = body
-		.sender_user
-		.as_ref()
-		.ok_or_else(|| Error::BadRequest(ErrorKind::MissingToken, "Missing access token."))?;
+	if !services.server.config.allow_password_change {
+		return Err!(Request(Forbidden("Password changes are disabled on this homeserver.")));
+	}
+
+	// Users who forgot their password authenticate through their email address
+	let Some(sender_user) = body.sender_user.as_ref() else {
+		return reset_password_via_email(&services, client, body).await;
+	};
 	let sender_device = body.sender_device.as_ref().expect("user is authenticated");
 
 	let mut uiaainfo = UiaaInfo {
@@ -583,6 +662,96 @@ pub(crate) async fn change_password_route(
 	Ok(change_password::v3::Response {})
 }
 
+/// Changes the password of a user who isn't logged in, after they validated
+/// the email address associated with their account.
+async fn reset_password_via_email(
+	services: &Services,
+	client: std::net::IpAddr,
+	body: Ruma<change_password::v3::Request>,
+) -> Result<change_password::v3::Response> {
+	if !services.email.is_enabled() {
+		return Err(Error::BadRequest(ErrorKind::MissingToken, "Missing access token."));
+	}
+
+	let anonymous = UserId::parse_with_server_name("", services.globals.server_name())
+		.expect("we know this is valid");
+
+	let mut uiaainfo = UiaaInfo {
+		flows: vec![AuthFlow { stages: vec![AuthType::EmailIdentity] }],
+		completed: Vec::new(),
+		params: Box::default(),
+		session: None,
+		auth_error: None,
+	};
+
+	let email = if let Some(auth) = &body.auth {
+		let (worked, uiaainfo) = services
+			.uiaa
+			.try_auth(&anonymous, "".into(), auth, &uiaainfo)
+			.await?;
+
+		if !worked {
+			return Err(Error::Uiaa(uiaainfo));
+		}
+
+		uiaainfo
+			.session
+			.as_deref()
+			.and_then(|session| {
+				services
+					.uiaa
+					.take_validated_email(&anonymous, "".into(), session)
+			})
+			.ok_or_else(|| {
+				err!(Request(ThreepidAuthFailed("Email address was not validated.")))
+			})?
+	} else if let Some(json) = body.json_body {
+		uiaainfo.session = Some(utils::random_string(SESSION_ID_LENGTH));
+		services
+			.uiaa
+			.create(&anonymous, "".into(), &uiaainfo, &json);
+
+		return Err(Error::Uiaa(uiaainfo));
+	} else {
+		return Err(Error::BadRequest(ErrorKind::NotJson, "Not json."));
+	};
+
+	let user_id = services.email.user_for_email(&email).await.map_err(|_| {
+		err!(Request(ThreepidNotFound("Email address is not associated with an account.")))
+	})?;
+
+	if services.users.is_deactivated(&user_id).await? {
+		return Err!(Request(UserDeactivated("The user has been deactivated")));
+	}
+
+	services
+		.users
+		.set_password(&user_id, Some(&body.new_password))?;
+
+	if body.logout_devices {
+		services
+			.users
+			.all_device_ids(&user_id)
+			.for_each(|id| services.users.remove_device(&user_id, id))
+			.await;
+	}
+
+	info!("User {user_id} reset their password through their email address.");
+
+	if services.server.config.admin_room_notices {
+		services
+			.admin
+			.send_message(RoomMessageEventContent::notice_plain(format!(
+				"User {user_id} reset their password through their email address from IP \
+				 {client}."
+			)))
+			.await
+			.ok();
+	}
+
+	Ok(change_password::v3::Response {})
+}
+
 /// # `GET _matrix/client/r0/account/whoami`
 ///
 /// Get `user_id` of the sender user.
@@ -733,24 +902,134 @@ pub(crate) async fn request_3pid_management_token_via_msisdn_route(
 	))
 }
 
+/// # `POST /_matrix/client/v3/register/email/requestToken`
+///
+/// "The homeserver must check that the given email address is not already
+/// associated with an account on this homeserver"
+///
+/// - Sends a validation link to the email address
+/// - 403 signals that email verification is disabled on this homeserver
+pub(crate) async fn request_registration_token_via_email_route(
+	State(services): State<crate::State>,
+	InsecureClientIp(client): InsecureClientIp,
+	body: Ruma<request_registration_token_via_email::v3::Request>,
+) -> Result<request_registration_token_via_email::v3::Response> {
+	if !services.globals.allow_registration() {
+		return Err!(Request(Forbidden("Registration has been disabled.")));
+	}
+
+	if services.email.user_for_email(&body.email).await.is_ok() {
+		return Err!(Request(ThreepidInUse("Email address is already in use.")));
+	}
+
+	let sid = services
+		.email
+		.request_token(
+			&body.email,
+			body.client_secret.as_str(),
+			body.send_attempt,
+			body.next_link.as_deref(),
+			client,
+		)
+		.await?;
+
+	Ok(request_registration_token_via_email::v3::Response::new(sid.try_into()?))
+}
+
+/// # `POST /_matrix/client/v3/account/password/email/requestToken`
+///
+/// "The homeserver should validate the email itself, either by sending a
+/// validation email itself or by using a service it has control over"
+///
+/// - Sends a validation link to the email address
+/// - 403 signals that email verification is disabled on this homeserver
+pub(crate) async fn request_password_change_token_via_email_route(
+	State(services): State<crate::State>,
+	InsecureClientIp(client): InsecureClientIp,
+	body: Ruma<request_password_change_token_via_email::v3::Request>,
+) -> Result<request_password_change_token_via_email::v3::Response> {
+	if !services.server.config.allow_password_change {
+		return Err!(Request(Forbidden("Password changes are disabled on this homeserver.")));
+	}
+
+	if services.email.user_for_email(&body.email).await.is_err() {
+		return Err!(Request(ThreepidNotFound(
+			"Email address is not associated with an account."
+		)));
+	}
+
+	let sid = services
+		.email
+		.request_token(
+			&body.email,
+			body.client_secret.as_str(),
+			body.send_attempt,
+			body.next_link.as_deref(),
+			client,
+		)
+		.await?;
+
+	Ok(request_password_change_token_via_email::v3::Response::new(sid.try_into()?))
+}
+
+#[derive(Deserialize)]
+struct ValidateEmailParams {
+	sid: String,
+	client_secret: String,
+	token: String,
+}
+
+/// # `GET /_conduwuit/email/validate`
+///
+/// Where the links in validation emails lead. Marks the session as validated
+/// and links to the `next_link` their client asked for.
+pub(crate) async fn validate_email_route(
+	State(services): State<crate::State>,
+	uri: Uri,
+) -> Result<Html<String>> {
+	let params: ValidateEmailParams = serde_html_form::from_str(uri.query().unwrap_or_default())
+		.map_err(|e| err!(Request(InvalidParam("Invalid validation link: {e}"))))?;
+
+	let ValidateEmailParams { sid, client_secret, token } = params;
+	let next_link = services.email.submit_token(&sid, &client_secret, &token)?;
+
+	let mut page = "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Email address \
+	                validated</title></head><body><p>Your email address has been validated. \
+	                You can now return to your client.</p>"
+		.to_owned();
+
+	if let Some(next_link) = next_link {
+		write!(page, "<p><a href=\"{}\">Continue</a></p>", HtmlEscape(next_link.as_str()))
+			.expect("should be able to write to string buffer");
+	}
+
+	page.push_str("</body></html>");
+
+	Ok(Html(page))
+}
+
 /// # `GET /_matrix/client/v1/register/m.login.registration_token/validity`
 ///
 /// Checks if the provided registration token is valid at the time of checking
 ///
-/// Currently does not have any ratelimiting, and this isn't very practical as
-/// there is only one registration token allowed.
+/// Currently does not have any ratelimiting.
 pub(crate) async fn check_registration_token_validity(
 	State(services): State<crate::State>,
 	body: Ruma<check_registration_token_validity::v1::Request>,
 ) -> Result<check_registration_token_validity::v1::Response> {
-	let Some(reg_token) = services.globals.registration_token.clone() else {
+	if !services.uiaa.registration_token_required().await {
 		return Err(Error::BadRequest(
 			ErrorKind::forbidden(),
 			"Server does not allow token registration.",
 		));
-	};
+	}
+
+	let valid = services
+		.uiaa
+		.is_valid_registration_token(&body.token)
+		.await;
 
-	Ok(check_registration_token_validity::v1::Response { valid: reg_token == body.token })
+	Ok(check_registration_token_validity::v1::Response { valid })
 }
 
 /// Runs through all the deactivation steps:
@@ -766,6 +1045,7 @@ pub async fn full_user_deactivate(
 	all_joined_rooms: &[OwnedRoomId],
 ) -> Result<()> {
 	services.users.deactivate_account(user_id).await.ok();
+	services.email.remove_email(user_id).await;
 	super::update_displayname(services, user_id, None, all_joined_rooms).await;
 	super::update_avatar_url(services, user_id, None, None, all_joined_rooms).await;
 
@@ -4,8 +4,9 @@
 use conduwuit::{Result, Server};
 use ruma::{
 	api::client::discovery::get_capabilities::{
-		self, Capabilities, GetLoginTokenCapability, RoomVersionStability,
-		RoomVersionsCapability, ThirdPartyIdChangesCapability,
+		self, Capabilities, ChangePasswordCapability, GetLoginTokenCapability,
+		RoomVersionStability, RoomVersionsCapability, SetAvatarUrlCapability,
+		SetDisplayNameCapability, ThirdPartyIdChangesCapability,
 	},
 	RoomVersionId,
 };
@@ -24,17 +25,26 @@ pub(crate) async fn get_capabilities_route(
 	let available: BTreeMap<RoomVersionId, RoomVersionStability> =
 		Server::available_room_versions().collect();
 
+	let config = &services.server.config;
 	let mut capabilities = Capabilities::default();
 	capabilities.room_versions = RoomVersionsCapability {
-		default: services.server.config.default_room_version.clone(),
+		default: config.default_room_version.clone(),
 		available,
 	};
 
+	capabilities.change_password =
+		ChangePasswordCapability { enabled: config.allow_password_change };
+
+	capabilities.set_displayname =
+		SetDisplayNameCapability { enabled: config.allow_displayname_change };
+
+	capabilities.set_avatar_url = SetAvatarUrlCapability { enabled: config.allow_avatar_change };
+
 	// we do not implement 3PID stuff
 	capabilities.thirdparty_id_changes = ThirdPartyIdChangesCapability { enabled: false };
 
 	capabilities.get_login_token = GetLoginTokenCapability {
-		enabled: services.server.config.login_via_existing_session,
+		enabled: config.login_via_existing_session,
 	};
 
 	// MSC4133 capability
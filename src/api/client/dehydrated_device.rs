@@ -0,0 +1,107 @@
+use axum::extract::State;
+use conduwuit::{err, Err, Result};
+use futures::StreamExt;
+use ruma::api::client::dehydrated_device::{
+	delete_dehydrated_device, get_dehydrated_device, get_events, put_dehydrated_device,
+};
+
+use crate::Ruma;
+
+/// # `PUT /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device`
+///
+/// Creates or replaces the user's dehydrated device.
+pub(crate) async fn put_dehydrated_device_route(
+	State(services): State<crate::State>,
+	body: Ruma<put_dehydrated_device::unstable::Request>,
+) -> Result<put_dehydrated_device::unstable::Response> {
+	let sender_user = body.sender_user().to_owned();
+	let device_id = body.body.device_id.clone();
+
+	services
+		.users
+		.set_dehydrated_device(&sender_user, body.body)
+		.await?;
+
+	Ok(put_dehydrated_device::unstable::Response::new(device_id))
+}
+
+/// # `GET /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device`
+///
+/// Gets the user's dehydrated device.
+pub(crate) async fn get_dehydrated_device_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_dehydrated_device::unstable::Request>,
+) -> Result<get_dehydrated_device::unstable::Response> {
+	let device = services
+		.users
+		.get_dehydrated_device(body.sender_user())
+		.await
+		.map_err(|_| err!(Request(NotFound("No dehydrated device is stored."))))?;
+
+	Ok(get_dehydrated_device::unstable::Response::new(
+		device.device_id,
+		device.device_data,
+	))
+}
+
+/// # `DELETE /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device`
+///
+/// Deletes the user's dehydrated device.
+pub(crate) async fn delete_dehydrated_device_route(
+	State(services): State<crate::State>,
+	body: Ruma<delete_dehydrated_device::unstable::Request>,
+) -> Result<delete_dehydrated_device::unstable::Response> {
+	let device_id = services
+		.users
+		.remove_dehydrated_device(body.sender_user())
+		.await
+		.map_err(|_| err!(Request(NotFound("No dehydrated device is stored."))))?;
+
+	Ok(delete_dehydrated_device::unstable::Response::new(device_id))
+}
+
+/// # `POST /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device/{deviceId}/events`
+///
+/// Gets the to-device events sent to the user's dehydrated device.
+///
+/// - Events up to the given `next_batch` have been received and are deleted
+pub(crate) async fn get_dehydrated_events_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_events::unstable::Request>,
+) -> Result<get_events::unstable::Response> {
+	let sender_user = body.sender_user();
+
+	if !services
+		.users
+		.is_dehydrated_device(sender_user, &body.device_id)
+		.await
+	{
+		return Err!(Request(Forbidden("Device is not the user's dehydrated device.")));
+	}
+
+	let since: Option<u64> = body
+		.next_batch
+		.as_deref()
+		.map(str::parse)
+		.transpose()
+		.map_err(|_| err!(Request(InvalidParam("Invalid next_batch token."))))?;
+
+	if let Some(since) = since {
+		services
+			.users
+			.remove_to_device_events(sender_user, &body.device_id, since)
+			.await;
+	}
+
+	let next_batch = services.globals.current_count()?;
+	let events: Vec<_> = services
+		.users
+		.get_to_device_events(sender_user, &body.device_id, since, Some(next_batch))
+		.collect()
+		.await;
+
+	let mut response = get_events::unstable::Response::new(events);
+	response.next_batch = (!response.events.is_empty()).then(|| next_batch.to_string());
+
+	Ok(response)
+}
@@ -1,7 +1,7 @@
 use std::collections::{BTreeMap, HashMap, HashSet};
 
 use axum::extract::State;
-use conduwuit::{err, utils, Error, Result};
+use conduwuit::{debug_warn, err, utils, Err, Error, Result};
 use futures::{stream::FuturesUnordered, StreamExt};
 use ruma::{
 	api::{
@@ -15,14 +15,20 @@
 		},
 		federation,
 	},
-	serde::Raw,
-	OneTimeKeyAlgorithm, OwnedDeviceId, OwnedUserId, UserId,
+	encryption::DeviceKeys,
+	serde::{Base64, Raw},
+	DeviceId, DeviceKeyAlgorithm, DeviceKeyId, OneTimeKeyAlgorithm, OwnedDeviceId, OwnedUserId,
+	UserId,
 };
 use serde_json::json;
 
 use super::SESSION_ID_LENGTH;
 use crate::{
-	service::{users::parse_master_key, Services},
+	service::{
+		server_keys::{PubKeyMap, PubKeys},
+		users::parse_master_key,
+		Services,
+	},
 	Ruma,
 };
 
@@ -47,6 +53,8 @@ pub(crate) async fn upload_keys_route(
 	}
 
 	if let Some(device_keys) = &body.device_keys {
+		check_device_keys(device_keys, sender_user, sender_device)?;
+
 		// TODO: merge this and the existing event?
 		// This check is needed to assure that signatures are kept
 		if services
@@ -70,6 +78,44 @@ pub(crate) async fn upload_keys_route(
 	})
 }
 
+/// Checks that uploaded device keys belong to the uploading device and are
+/// signed by the device's own ed25519 key.
+fn check_device_keys(
+	device_keys: &Raw<DeviceKeys>,
+	user_id: &UserId,
+	device_id: &DeviceId,
+) -> Result {
+	let keys: DeviceKeys = device_keys
+		.deserialize()
+		.map_err(|e| err!(Request(BadJson(debug_warn!("Invalid device keys: {e}")))))?;
+
+	if keys.user_id != user_id || keys.device_id != device_id {
+		return Err!(Request(InvalidParam("Device keys do not belong to this device.")));
+	}
+
+	let key_id = DeviceKeyId::from_parts(DeviceKeyAlgorithm::Ed25519, device_id);
+	let Some(key) = keys.keys.get(&key_id) else {
+		return Err!(Request(InvalidParam(
+			"Device keys do not contain the device's ed25519 key."
+		)));
+	};
+
+	let key = Base64::parse(key).map_err(|e| {
+		err!(Request(InvalidParam("Device ed25519 key is not valid base64: {e}")))
+	})?;
+
+	let keys: PubKeys = [(key_id.to_string(), key)].into();
+	let keys: PubKeyMap = [(user_id.as_str().into(), keys)].into();
+	let device_keys = utils::to_canonical_object(device_keys)
+		.map_err(|e| err!(Request(BadJson("Invalid device keys: {e}"))))?;
+
+	ruma::signatures::verify_json(&keys, device_keys).map_err(|e| {
+		err!(Request(InvalidParam(debug_warn!(
+			"Device keys are not signed by the device: {e}"
+		))))
+	})
+}
+
 /// # `POST /_matrix/client/r0/keys/query`
 ///
 /// Get end-to-end encryption keys for the given users.
@@ -108,7 +154,8 @@ pub(crate) async fn claim_keys_route(
 ///
 /// Uploads end-to-end key information for the sender user.
 ///
-/// - Requires UIAA to verify password
+/// - Requires UIAA to verify password, unless the user has no master key yet
+///   (MSC3967)
 pub(crate) async fn upload_signing_keys_route(
 	State(services): State<crate::State>,
 	body: Ruma<upload_signing_keys::v3::Request>,
@@ -116,48 +163,54 @@ pub(crate) async fn upload_signing_keys_route(
 	let sender_user = body.sender_user.as_ref().expect("user is authenticated");
 	let sender_device = body.sender_device.as_ref().expect("user is authenticated");
 
-	// UIAA
-	let mut uiaainfo = UiaaInfo {
-		flows: vec![AuthFlow { stages: vec![AuthType::Password] }],
-		completed: Vec::new(),
-		params: Box::default(),
-		session: None,
-		auth_error: None,
-	};
+	let first_upload = services
+		.users
+		.get_master_key(None, sender_user, &|_| false)
+		.await
+		.is_err();
 
-	if let Some(auth) = &body.auth {
-		let (worked, uiaainfo) = services
-			.uiaa
-			.try_auth(sender_user, sender_device, auth, &uiaainfo)
-			.await?;
+	// UIAA
+	if !first_upload {
+		let mut uiaainfo = UiaaInfo {
+			flows: vec![AuthFlow { stages: vec![AuthType::Password] }],
+			completed: Vec::new(),
+			params: Box::default(),
+			session: None,
+			auth_error: None,
+		};
+
+		if let Some(auth) = &body.auth {
+			let (worked, uiaainfo) = services
+				.uiaa
+				.try_auth(sender_user, sender_device, auth, &uiaainfo)
+				.await?;
+
+			if !worked {
+				return Err(Error::Uiaa(uiaainfo));
+			}
+		// Success!
+		} else if let Some(json) = body.json_body {
+			uiaainfo.session = Some(utils::random_string(SESSION_ID_LENGTH));
+			services
+				.uiaa
+				.create(sender_user, sender_device, &uiaainfo, &json);
 
-		if !worked {
 			return Err(Error::Uiaa(uiaainfo));
+		} else {
+			return Err(Error::BadRequest(ErrorKind::NotJson, "Not json."));
 		}
-	// Success!
-	} else if let Some(json) = body.json_body {
-		uiaainfo.session = Some(utils::random_string(SESSION_ID_LENGTH));
-		services
-			.uiaa
-			.create(sender_user, sender_device, &uiaainfo, &json);
-
-		return Err(Error::Uiaa(uiaainfo));
-	} else {
-		return Err(Error::BadRequest(ErrorKind::NotJson, "Not json."));
 	}
 
-	if let Some(master_key) = &body.master_key {
-		services
-			.users
-			.add_cross_signing_keys(
-				sender_user,
-				master_key,
-				&body.self_signing_key,
-				&body.user_signing_key,
-				true, // notify so that other users see the new keys
-			)
-			.await?;
-	}
+	services
+		.users
+		.add_cross_signing_keys(
+			sender_user,
+			body.master_key.as_ref(),
+			&body.self_signing_key,
+			&body.user_signing_key,
+			true, // notify so that other users see the new keys
+		)
+		.await?;
 
 	Ok(upload_signing_keys::v3::Response {})
 }
@@ -402,7 +455,10 @@ pub(crate) async fn get_keys_helper<F>(
 				services
 					.users
 					.add_cross_signing_keys(
-						&user, &raw, &None, &None,
+						&user,
+						Some(&raw),
+						&None,
+						&None,
 						false, /* Dont notify. A notification would trigger another key request
 						       * resulting in an endless loop */
 					)
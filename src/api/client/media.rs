@@ -4,11 +4,14 @@
 use axum_client_ip::InsecureClientIp;
 use conduwuit::{
 	err,
+	result::LogErr,
 	utils::{self, content_disposition::make_content_disposition, math::ruma_from_usize},
 	Err, Result,
 };
 use conduwuit_service::{
-	media::{Dim, FileMeta, CACHE_CONTROL_IMMUTABLE, CORP_CROSS_ORIGIN, MXC_LENGTH},
+	media::{
+		create_blurhash, Dim, FileMeta, CACHE_CONTROL_IMMUTABLE, CORP_CROSS_ORIGIN, MXC_LENGTH,
+	},
 	Services,
 };
 use reqwest::Url;
@@ -63,13 +66,61 @@ pub(crate) async fn create_content_route(
 	};
 
 	services
+		.media
+		.use_upload_quota(user, body.file.len())
+		.await?;
+
+	if let Err(e) = services
 		.media
 		.create(&mxc, Some(user), Some(&content_disposition), content_type, &body.file)
 		.await
-		.map(|()| create_content::v3::Response {
-			content_uri: mxc.to_string().into(),
-			blurhash: None,
-		})
+	{
+		services
+			.media
+			.refund_upload_quota(user, body.file.len())
+			.await;
+
+		return Err(e);
+	}
+
+	if services.server.config.freeze_unauthenticated_media {
+		services.media.set_authenticated_only(&mxc);
+	}
+
+	let is_image = content_type.is_some_and(|content_type| content_type.starts_with("image/"));
+
+	// Generating every size takes a while; don't hold up the upload response.
+	if is_image && services.server.config.media_thumbnail_pregenerate {
+		let media = services.media.clone();
+		let server_name = mxc.server_name.to_owned();
+		let media_id = mxc.media_id.to_owned();
+		services.server.runtime().spawn(async move {
+			let mxc = Mxc {
+				server_name: &server_name,
+				media_id: &media_id,
+			};
+
+			media.pregenerate_thumbnails(&mxc).await.log_err().ok();
+		});
+	}
+
+	// Decoding the image is CPU-bound; keep it off the executor.
+	let generate_blurhash = body.generate_blurhash && services.server.config.media_blurhash;
+	let blurhash = if is_image && generate_blurhash {
+		let file = body.body.file;
+		services
+			.server
+			.runtime()
+			.spawn_blocking(move || create_blurhash(&file))
+			.await?
+	} else {
+		None
+	};
+
+	Ok(create_content::v3::Response {
+		content_uri: mxc.to_string().into(),
+		blurhash,
+	})
 }
 
 /// # `GET /_matrix/client/v1/media/thumbnail/{serverName}/{mediaId}`
@@ -142,6 +142,10 @@ pub(crate) async fn get_content_legacy_route(
 		media_id: &body.media_id,
 	};
 
+	if services.media.is_authenticated_only(&mxc).await {
+		return Err!(Request(NotFound("Media not found.")));
+	}
+
 	if let Some(FileMeta {
 		content,
 		content_type,
@@ -227,6 +231,10 @@ pub(crate) async fn get_content_as_filename_legacy_route(
 		media_id: &body.media_id,
 	};
 
+	if services.media.is_authenticated_only(&mxc).await {
+		return Err!(Request(NotFound("Media not found.")));
+	}
+
 	if let Some(FileMeta {
 		content,
 		content_type,
@@ -314,6 +322,10 @@ pub(crate) async fn get_content_thumbnail_legacy_route(
 		media_id: &body.media_id,
 	};
 
+	if services.media.is_authenticated_only(&mxc).await {
+		return Err!(Request(NotFound("Media not found.")));
+	}
+
 	let dim = Dim::from_ruma(body.width, body.height, body.method.clone())?;
 	if let Some(FileMeta {
 		content,
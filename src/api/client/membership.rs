@@ -9,7 +9,7 @@
 use axum::extract::State;
 use axum_client_ip::InsecureClientIp;
 use conduwuit::{
-	at, debug, debug_info, debug_warn, err, info,
+	debug, debug_info, debug_warn, err, info,
 	pdu::{gen_event_id_canonical_json, PduBuilder},
 	result::FlatOk,
 	trace,
@@ -23,8 +23,9 @@
 			error::ErrorKind,
 			knock::knock_room,
 			membership::{
-				ban_user, forget_room, get_member_events, invite_user, join_room_by_id,
-				join_room_by_id_or_alias,
+				ban_user, forget_room,
+				get_member_events::{self, v3::MembershipEventFilter},
+				invite_user, join_room_by_id, join_room_by_id_or_alias,
 				joined_members::{self, v3::RoomMember},
 				joined_rooms, kick_user, leave_room, unban_user, ThirdPartySigned,
 			},
@@ -38,7 +39,7 @@
 			member::{MembershipState, RoomMemberEventContent},
 			message::RoomMessageEventContent,
 		},
-		StateEventType,
+		StateEventType, TimelineEventType,
 	},
 	state_res, CanonicalJsonObject, CanonicalJsonValue, OwnedEventId, OwnedRoomId,
 	OwnedServerName, OwnedUserId, RoomId, RoomVersionId, ServerName, UserId,
@@ -745,8 +746,8 @@ pub(crate) async fn joined_rooms_route(
 
 /// # `POST /_matrix/client/r0/rooms/{roomId}/members`
 ///
-/// Lists all joined users in a room (TODO: at a specific point in time, with a
-/// specific membership).
+/// Lists the membership events of a room, optionally at the point in time of
+/// the `at` sync token and filtered by membership.
 ///
 /// - Only works if the user is currently joined
 pub(crate) async fn get_member_events_route(
@@ -764,20 +765,55 @@ pub(crate) async fn get_member_events_route(
 		return Err!(Request(Forbidden("You don't have permission to view this room.")));
 	}
 
+	let shortstatehash = match body.at.as_deref() {
+		| Some(at) => {
+			let at = at
+				.parse()
+				.map_err(|_| err!(Request(InvalidParam("Invalid `at` sync token."))))?;
+
+			services
+				.rooms
+				.user
+				.get_token_shortstatehash(&body.room_id, at)
+				.await?
+		},
+		| None => services
+			.rooms
+			.state
+			.get_room_shortstatehash(&body.room_id)
+			.await?,
+	};
+
+	let membership = body.membership.as_ref();
+	let not_membership = body.not_membership.as_ref();
+
 	Ok(get_member_events::v3::Response {
 		chunk: services
 			.rooms
 			.state_accessor
-			.room_state_full(&body.room_id)
-			.ready_filter_map(Result::ok)
-			.ready_filter(|((ty, _), _)| *ty == StateEventType::RoomMember)
-			.map(at!(1))
+			.state_full_pdus(shortstatehash)
+			.ready_filter(|pdu| pdu.kind == TimelineEventType::RoomMember)
+			.ready_filter(|pdu| membership_filter(pdu, membership, not_membership))
 			.map(PduEvent::into_member_event)
 			.collect()
 			.await,
 	})
 }
 
+fn membership_filter(
+	pdu: &PduEvent,
+	for_membership: Option<&MembershipEventFilter>,
+	not_membership: Option<&MembershipEventFilter>,
+) -> bool {
+	let Ok(content) = pdu.get_content::<RoomMemberEventContent>() else {
+		return false;
+	};
+
+	let membership = content.membership.as_str();
+	for_membership.is_none_or(|filter| filter.as_str() == membership)
+		&& not_membership.is_none_or(|filter| filter.as_str() != membership)
+}
+
 /// # `POST /_matrix/client/r0/rooms/{roomId}/joined_members`
 ///
 /// Lists all members of a room.
@@ -799,16 +835,32 @@ pub(crate) async fn joined_members_route(
 		return Err!(Request(Forbidden("You don't have permission to view this room.")));
 	}
 
+	let room_id = &body.room_id;
 	let joined: BTreeMap<OwnedUserId, RoomMember> = services
 		.rooms
 		.state_cache
-		.room_members(&body.room_id)
+		.room_members(room_id)
 		.map(ToOwned::to_owned)
 		.then(|user| async move {
-			(user.clone(), RoomMember {
-				display_name: services.users.displayname(&user).await.ok(),
-				avatar_url: services.users.avatar_url(&user).await.ok(),
-			})
+			// Prefer the room-specific profile from the user's membership event
+			let member = services
+				.rooms
+				.state_accessor
+				.get_member(room_id, &user)
+				.await;
+
+			let room_member = match member {
+				| Ok(content) => RoomMember {
+					display_name: content.displayname,
+					avatar_url: content.avatar_url,
+				},
+				| Err(_) => RoomMember {
+					display_name: services.users.displayname(&user).await.ok(),
+					avatar_url: services.users.avatar_url(&user).await.ok(),
+				},
+			};
+
+			(user, room_member)
 		})
 		.collect()
 		.await;
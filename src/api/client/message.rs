@@ -114,7 +114,7 @@ pub(crate) async fn get_message_events_route(
 			.boxed(),
 	};
 
-	let events: Vec<_> = it
+	let mut events: Vec<_> = it
 		.ready_take_while(|(count, _)| Some(*count) != to)
 		.ready_filter_map(|item| event_filter(item, filter))
 		.wide_filter_map(|item| ignored_filter(&services, item, sender_user))
@@ -123,6 +123,14 @@ pub(crate) async fn get_message_events_route(
 		.collect()
 		.await;
 
+	services
+		.rooms
+		.pdu_metadata
+		.add_bundled_aggregations(sender_user, room_id, &mut events)
+		.await
+		.log_err()
+		.ok();
+
 	let lazy_loading_context = lazy_loading::Context {
 		user_id: sender_user,
 		device_id: sender_device,
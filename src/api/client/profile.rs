@@ -40,6 +40,10 @@ pub(crate) async fn set_displayname_route(
 		return Err!(Request(Forbidden("You cannot update the profile of another user")));
 	}
 
+	if !services.server.config.allow_displayname_change && body.appservice_info.is_none() {
+		return Err!(Request(Forbidden("Displayname changes are disabled on this homeserver.")));
+	}
+
 	let all_joined_rooms: Vec<OwnedRoomId> = services
 		.rooms
 		.state_cache
@@ -129,6 +133,10 @@ pub(crate) async fn set_avatar_url_route(
 		return Err!(Request(Forbidden("You cannot update the profile of another user")));
 	}
 
+	if !services.server.config.allow_avatar_change && body.appservice_info.is_none() {
+		return Err!(Request(Forbidden("Avatar changes are disabled on this homeserver.")));
+	}
+
 	let all_joined_rooms: Vec<OwnedRoomId> = services
 		.rooms
 		.state_cache
@@ -46,7 +46,8 @@ pub(crate) async fn set_read_marker_route(
 		services
 			.rooms
 			.user
-			.reset_notification_counts(sender_user, &body.room_id);
+			.reset_notification_counts(sender_user, &body.room_id)
+			.await;
 	}
 
 	// ping presence
@@ -115,14 +116,29 @@ pub(crate) async fn create_receipt_route(
 ) -> Result<create_receipt::v3::Response> {
 	let sender_user = body.sender_user();
 
+	// Receipts inside a thread only mark that thread as read, and receipts for
+	// the main timeline leave the threads unread
 	if matches!(
 		&body.receipt_type,
 		create_receipt::v3::ReceiptType::Read | create_receipt::v3::ReceiptType::ReadPrivate
 	) {
-		services
-			.rooms
-			.user
-			.reset_notification_counts(sender_user, &body.room_id);
+		match &body.thread {
+			| ReceiptThread::Thread(thread) => services
+				.rooms
+				.user
+				.reset_thread_notification_counts(sender_user, &body.room_id, thread),
+			| ReceiptThread::Main => services
+				.rooms
+				.user
+				.reset_main_notification_counts(sender_user, &body.room_id),
+			| _ => {
+				services
+					.rooms
+					.user
+					.reset_notification_counts(sender_user, &body.room_id)
+					.await;
+			},
+		}
 	}
 
 	// ping presence
@@ -159,7 +175,7 @@ pub(crate) async fn create_receipt_route(
 						sender_user.to_owned(),
 						ruma::events::receipt::Receipt {
 							ts: Some(MilliSecondsSinceUnixEpoch::now()),
-							thread: ReceiptThread::Unthreaded,
+							thread: body.thread.clone(),
 						},
 					)]),
 				)]),
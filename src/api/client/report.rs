@@ -10,13 +10,13 @@
 		room::{report_content, report_room},
 	},
 	events::room::message,
-	int, EventId, RoomId, UserId,
+	int, EventId, MilliSecondsSinceUnixEpoch, RoomId, UserId,
 };
 use tokio::time::sleep;
 
 use crate::{
 	debug_info,
-	service::{pdu::PduEvent, Services},
+	service::{pdu::PduEvent, rooms::reports::Report, Services},
 	Error, Result, Ruma,
 };
 
@@ -58,12 +58,22 @@ pub(crate) async fn report_room_route(
 		)));
 	}
 
+	let report_id = services.rooms.reports.add_report(&Report {
+		reporter: sender_user.clone(),
+		room_id: body.room_id.clone(),
+		event_id: None,
+		reason: body.reason.clone(),
+		score: None,
+		received_ts: MilliSecondsSinceUnixEpoch::now(),
+	})?;
+
 	// send admin room message that we received the report with an @room ping for
 	// urgency
 	services
 		.admin
 		.send_message(message::RoomMessageEventContent::text_markdown(format!(
-			"@room Room report received from {} -\n\nRoom ID: {}\n\nReport Reason: {}",
+			"@room Room report {report_id} received from {} -\n\nRoom ID: {}\n\nReport \
+			 Reason: {}",
 			sender_user.to_owned(),
 			body.room_id,
 			body.reason.as_deref().unwrap_or("")
@@ -112,15 +122,25 @@ pub(crate) async fn report_event_route(
 	)
 	.await?;
 
+	let report_id = services.rooms.reports.add_report(&Report {
+		reporter: sender_user.clone(),
+		room_id: pdu.room_id.clone(),
+		event_id: Some(pdu.event_id.clone()),
+		reason: body.reason.clone(),
+		score: body.score,
+		received_ts: MilliSecondsSinceUnixEpoch::now(),
+	})?;
+
 	// send admin room message that we received the report with an @room ping for
 	// urgency
 	services
 		.admin
 		.send_message(message::RoomMessageEventContent::text_markdown(format!(
-			"@room Event report received from {} -\n\nEvent ID: {}\nRoom ID: {}\nSent By: \
-			 {}\n\nReport Score: {}\nReport Reason: {}",
+			"@room Event report {report_id} received from {} -\n\nEvent ID: [{}]({})\nRoom \
+			 ID: {}\nSent By: {}\n\nReport Score: {}\nReport Reason: {}",
 			sender_user.to_owned(),
 			pdu.event_id,
+			pdu.room_id.matrix_to_event_uri(pdu.event_id.clone()),
 			pdu.room_id,
 			pdu.sender,
 			body.score.unwrap_or_else(|| ruma::Int::from(0)),
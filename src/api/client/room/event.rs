@@ -1,5 +1,5 @@
 use axum::extract::State;
-use conduwuit::{err, Err, Event, Result};
+use conduwuit::{err, result::LogErr, Err, Event, Result};
 use futures::{try_join, FutureExt, TryFutureExt};
 use ruma::api::client::room::get_room_event;
 
@@ -16,13 +16,13 @@ pub(crate) async fn get_room_event_route(
 		.rooms
 		.timeline
 		.get_pdu(&body.event_id)
-		.map_err(|_| err!(Request(NotFound("Event {} not found.", &body.event_id))));
+		.map_err(|_| err!(Request(NotFound("Event not found"))));
 
 	let token = services
 		.rooms
 		.timeline
 		.get_pdu_count(&body.event_id)
-		.map_err(|_| err!(Request(NotFound("Event not found."))));
+		.map_err(|_| err!(Request(NotFound("Event not found"))));
 
 	let visible = services
 		.rooms
@@ -32,20 +32,32 @@ pub(crate) async fn get_room_event_route(
 
 	let (token, mut event, visible) = try_join!(token, event, visible)?;
 
+	if event.event_id() != &body.event_id || event.room_id() != body.room_id {
+		return Err!(Request(NotFound("Event not found")));
+	}
+
+	// The spec mandates the same response for events which are not visible as
+	// for events which don't exist, so their existence isn't leaked.
 	if !visible
 		|| ignored_filter(&services, (token, event.clone()), body.sender_user())
 			.await
 			.is_none()
 	{
-		return Err!(Request(Forbidden("You don't have permission to view this event.")));
-	}
-
-	if event.event_id() != &body.event_id || event.room_id() != body.room_id {
 		return Err!(Request(NotFound("Event not found")));
 	}
 
 	event.add_age().ok();
 
+	let mut events = [(token, event)];
+	services
+		.rooms
+		.pdu_metadata
+		.add_bundled_aggregations(body.sender_user(), &body.room_id, &mut events)
+		.await
+		.log_err()
+		.ok();
+
+	let [(_, event)] = events;
 	let event = event.to_room_event();
 
 	Ok(get_room_event::v3::Response { event })
@@ -41,6 +41,7 @@
 /// - Sender user joins the room
 /// - Transfers some state events
 /// - Moves local aliases
+/// - Moves the room directory listing
 /// - Modifies old room power levels to prevent users from speaking
 pub(crate) async fn upgrade_room_route(
 	State(services): State<crate::State>,
@@ -247,6 +248,17 @@ pub(crate) async fn upgrade_room_route(
 			.set_alias(alias, &replacement_room, sender_user)?;
 	}
 
+	// Moves the room directory listing to the new room
+	if services
+		.rooms
+		.directory
+		.is_public_room(&body.room_id)
+		.await
+	{
+		services.rooms.directory.set_public(&replacement_room);
+		services.rooms.directory.set_not_public(&body.room_id);
+	}
+
 	// Get the old room power levels
 	let power_levels_event_content: RoomPowerLevelsEventContent = services
 		.rooms
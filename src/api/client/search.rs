@@ -1,13 +1,13 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use axum::extract::State;
 use conduwuit::{
-	at, is_true,
+	is_true,
 	result::FlatOk,
 	utils::{stream::ReadyExt, IterStream},
 	Err, PduEvent, Result,
 };
-use futures::{future::OptionFuture, FutureExt, StreamExt, TryFutureExt, TryStreamExt};
+use futures::{future::OptionFuture, StreamExt, TryFutureExt, TryStreamExt};
 use ruma::{
 	api::client::search::search_events::{
 		self,
@@ -15,10 +15,13 @@
 	},
 	events::AnyStateEvent,
 	serde::Raw,
-	OwnedRoomId, RoomId, UInt, UserId,
+	OwnedRoomId, RoomId, UserId,
 };
 use search_events::v3::{Request, Response};
-use service::{rooms::search::RoomQuery, Services};
+use service::{
+	rooms::search::{RoomQuery, SearchResults},
+	Services,
+};
 
 use crate::Ruma;
 
@@ -27,7 +30,6 @@
 
 const LIMIT_DEFAULT: usize = 10;
 const LIMIT_MAX: usize = 100;
-const BATCH_MAX: usize = 20;
 
 /// # `POST /_matrix/client/r0/search`
 ///
@@ -71,15 +73,9 @@ async fn category_room_events(
 		.map(TryInto::try_into)
 		.flat_ok()
 		.unwrap_or(LIMIT_DEFAULT)
-		.min(LIMIT_MAX);
-
-	let next_batch: usize = next_batch
-		.map(str::parse)
-		.transpose()?
-		.unwrap_or(0)
-		.min(limit.saturating_mul(BATCH_MAX));
+		.clamp(1, LIMIT_MAX);
 
-	let rooms = filter
+	let rooms: Vec<OwnedRoomId> = filter
 		.rooms
 		.clone()
 		.map(IntoIterator::into_iter)
@@ -92,58 +88,45 @@ async fn category_room_events(
 				.rooms_joined(sender_user)
 				.map(ToOwned::to_owned)
 				.boxed()
-		});
-
-	let results: Vec<_> = rooms
+		})
 		.filter_map(|room_id| async move {
 			check_room_visible(services, sender_user, &room_id, criteria)
 				.await
 				.is_ok()
 				.then_some(room_id)
 		})
-		.filter_map(|room_id| async move {
-			let query = RoomQuery {
-				room_id: &room_id,
-				user_id: Some(sender_user),
-				criteria,
-				skip: next_batch,
-				limit,
-			};
-
-			let (count, results) = services.rooms.search.search_pdus(&query).await.ok()?;
-
-			results
-				.collect::<Vec<_>>()
-				.map(|results| (room_id.clone(), count, results))
-				.map(Some)
-				.await
-		})
 		.collect()
 		.await;
 
-	let total: UInt = results
-		.iter()
-		.fold(0, |a: usize, (_, count, _)| a.saturating_add(*count))
-		.try_into()?;
+	let query = RoomQuery {
+		rooms: &rooms,
+		user_id: sender_user,
+		criteria,
+		limit,
+		next_batch,
+	};
 
-	let state: RoomStates = results
+	let SearchResults { count, pdus, highlights, next_batch } =
+		services.rooms.search.search_pdus(&query).await?;
+
+	let state: RoomStates = pdus
 		.iter()
+		.map(|pdu| &pdu.room_id)
+		.collect::<BTreeSet<_>>()
+		.into_iter()
 		.stream()
 		.ready_filter(|_| criteria.include_state.is_some_and(is_true!()))
-		.filter_map(|(room_id, ..)| async move {
+		.filter_map(|room_id| async move {
 			procure_room_state(services, room_id)
-				.map_ok(|state| (room_id.clone(), state))
+				.map_ok(|state| (room_id.to_owned(), state))
 				.await
 				.ok()
 		})
 		.collect()
 		.await;
 
-	let results: Vec<SearchResult> = results
+	let results: Vec<SearchResult> = pdus
 		.into_iter()
-		.map(at!(2))
-		.flatten()
-		.stream()
 		.map(|pdu| pdu.to_room_event())
 		.map(|result| SearchResult {
 			rank: None,
@@ -156,22 +139,10 @@ async fn category_room_events(
 				end: None,                     //TODO
 			},
 		})
-		.collect()
-		.await;
-
-	let highlights = criteria
-		.search_term
-		.split_terminator(|c: char| !c.is_alphanumeric())
-		.map(str::to_lowercase)
 		.collect();
 
-	let next_batch = (results.len() >= limit)
-		.then_some(next_batch.saturating_add(results.len()))
-		.as_ref()
-		.map(ToString::to_string);
-
 	Ok(ResultRoomEvents {
-		count: Some(total),
+		count: Some(count.try_into()?),
 		next_batch,
 		results,
 		state,
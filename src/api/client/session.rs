@@ -11,23 +11,29 @@
 			get_login_token,
 			get_login_types::{
 				self,
-				v3::{ApplicationServiceLoginType, PasswordLoginType, TokenLoginType},
+				v3::{
+					ApplicationServiceLoginType, IdentityProvider, PasswordLoginType,
+					SsoLoginType, TokenLoginType,
+				},
 			},
 			login::{
 				self,
 				v3::{DiscoveryInfo, HomeserverInfo},
 			},
-			logout, logout_all,
+			logout, logout_all, refresh_token,
 		},
 		uiaa,
 	},
-	OwnedUserId, UserId,
+	serde::JsonObject,
+	CanonicalJsonValue, OwnedUserId, UserId,
 };
-use service::uiaa::SESSION_ID_LENGTH;
+use service::{uiaa::SESSION_ID_LENGTH, Services};
 
 use super::{DEVICE_ID_LENGTH, TOKEN_LENGTH};
 use crate::{utils, utils::hash, Error, Result, Ruma};
 
+const JWT_LOGIN_TYPE: &str = "org.matrix.login.jwt";
+
 /// # `GET /_matrix/client/v3/login`
 ///
 /// Get the supported login types of this server. One of these should be used as
@@ -38,13 +44,35 @@ pub(crate) async fn get_login_types_route(
 	InsecureClientIp(client): InsecureClientIp,
 	_body: Ruma<get_login_types::v3::Request>,
 ) -> Result<get_login_types::v3::Response> {
-	Ok(get_login_types::v3::Response::new(vec![
+	let mut flows = vec![
 		get_login_types::v3::LoginType::Password(PasswordLoginType::default()),
 		get_login_types::v3::LoginType::ApplicationService(ApplicationServiceLoginType::default()),
 		get_login_types::v3::LoginType::Token(TokenLoginType {
 			get_login_token: services.server.config.login_via_existing_session,
 		}),
-	]))
+	];
+
+	let config = &services.server.config;
+	if config.jwt_secret.is_some() || config.jwt_jwks_url.is_some() {
+		flows.push(get_login_types::v3::LoginType::new(JWT_LOGIN_TYPE, JsonObject::new())?);
+	}
+
+	if !config.sso_providers.is_empty() {
+		let identity_providers = config
+			.sso_providers
+			.iter()
+			.map(|provider| {
+				let name = provider.name.clone().unwrap_or_else(|| provider.id.clone());
+				let mut identity_provider = IdentityProvider::new(provider.id.clone(), name);
+				identity_provider.icon.clone_from(&provider.icon);
+				identity_provider
+			})
+			.collect();
+
+		flows.push(get_login_types::v3::LoginType::Sso(SsoLoginType { identity_providers }));
+	}
+
+	Ok(get_login_types::v3::Response::new(flows))
 }
 
 /// # `POST /_matrix/client/v3/login`
@@ -111,7 +139,8 @@ pub(crate) async fn login_route(
 		},
 		| login::v3::LoginInfo::Token(login::v3::Token { token }) => {
 			debug!("Got token login type");
-			if !services.server.config.login_via_existing_session {
+			let config = &services.server.config;
+			if !config.login_via_existing_session && config.sso_providers.is_empty() {
 				return Err!(Request(Unknown("Token login is not enabled.")));
 			}
 			services.users.find_from_login_token(token).await?
@@ -153,15 +182,29 @@ pub(crate) async fn login_route(
 				));
 			}
 
+			if !services.globals.user_is_local(&user_id) || !services.users.exists(&user_id).await
+			{
+				return Err!(Request(Forbidden("User does not exist on this server.")));
+			}
+
+			if services.users.is_deactivated(&user_id).await? {
+				return Err!(Request(UserDeactivated("The user has been deactivated")));
+			}
+
 			user_id
 		},
 		| _ => {
-			warn!("Unsupported or unknown login type: {:?}", &body.login_info);
-			debug!("JSON body: {:?}", &body.json_body);
-			return Err(Error::BadRequest(
-				ErrorKind::Unknown,
-				"Unsupported or unknown login type.",
-			));
+			let Some(token) = jwt_login_token(body.json_body.as_ref()) else {
+				warn!("Unsupported or unknown login type: {:?}", &body.login_info);
+				debug!("JSON body: {:?}", &body.json_body);
+				return Err(Error::BadRequest(
+					ErrorKind::Unknown,
+					"Unsupported or unknown login type.",
+				));
+			};
+
+			debug!("Got JWT login type");
+			validate_jwt(&services, token).await?
 		},
 	};
 
@@ -196,13 +239,25 @@ pub(crate) async fn login_route(
 			.create_device(
 				&user_id,
 				&device_id,
-				&token,
+				Some(token.as_str()),
 				body.initial_device_display_name.clone(),
 				Some(client.to_string()),
 			)
 			.await?;
 	}
 
+	let (refresh_token, expires_in) = if body.refresh_token {
+		let refresh_token = utils::random_string(TOKEN_LENGTH);
+		let expires_in = services
+			.users
+			.set_refresh_token(&user_id, &device_id, &token, &refresh_token)
+			.await;
+
+		(Some(refresh_token), Some(expires_in))
+	} else {
+		(None, None)
+	};
+
 	// send client well-known if specified so the client knows to reconfigure itself
 	let client_discovery_info: Option<DiscoveryInfo> = services
 		.server
@@ -223,9 +278,79 @@ pub(crate) async fn login_route(
 		access_token: token,
 		device_id,
 		well_known: client_discovery_info,
-		expires_in: None,
+		expires_in,
 		home_server: Some(services.globals.server_name().to_owned()),
-		refresh_token: None,
+		refresh_token,
+	})
+}
+
+/// Extracts the token from an `org.matrix.login.jwt` login request body.
+fn jwt_login_token(json_body: Option<&CanonicalJsonValue>) -> Option<&str> {
+	let body = json_body?.as_object()?;
+	if body.get("type")?.as_str()? != JWT_LOGIN_TYPE {
+		return None;
+	}
+
+	body.get("token")?.as_str()
+}
+
+/// Validates a JSON web token against the configured `jwt_secret` or
+/// `jwt_jwks_url`, returning the local user named by its `sub` claim.
+async fn validate_jwt(services: &Services, token: &str) -> Result<OwnedUserId> {
+	let config = &services.server.config;
+	if config.jwt_secret.is_none() && config.jwt_jwks_url.is_none() {
+		return Err!(Request(Unknown("JWT login is not enabled.")));
+	}
+
+	let claims = services.jwt.verify_login_token(token).await?;
+
+	let user_id =
+		UserId::parse_with_server_name(claims.sub.to_lowercase(), services.globals.server_name())
+			.map_err(|_| err!(Request(InvalidUsername("Username is invalid."))))?;
+
+	if !services.globals.user_is_local(&user_id) || !services.users.exists(&user_id).await {
+		return Err!(Request(Forbidden("User does not exist on this server.")));
+	}
+
+	if services.users.is_deactivated(&user_id).await? {
+		return Err!(Request(UserDeactivated("The user has been deactivated")));
+	}
+
+	Ok(user_id)
+}
+
+/// # `POST /_matrix/client/v3/refresh`
+///
+/// Exchanges a refresh token for a new access token and refresh token. The
+/// old refresh token and access token are invalidated.
+#[tracing::instrument(skip_all, fields(%client), name = "refresh")]
+pub(crate) async fn refresh_token_route(
+	State(services): State<crate::State>,
+	InsecureClientIp(client): InsecureClientIp,
+	body: Ruma<refresh_token::v3::Request>,
+) -> Result<refresh_token::v3::Response> {
+	let (user_id, device_id) = services
+		.users
+		.find_from_refresh_token(&body.refresh_token)
+		.await?;
+
+	let access_token = utils::random_string(TOKEN_LENGTH);
+	let refresh_token = utils::random_string(TOKEN_LENGTH);
+
+	services
+		.users
+		.set_token(&user_id, &device_id, &access_token)
+		.await?;
+
+	let expires_in = services
+		.users
+		.set_refresh_token(&user_id, &device_id, &access_token, &refresh_token)
+		.await;
+
+	Ok(refresh_token::v3::Response {
+		access_token,
+		refresh_token: Some(refresh_token),
+		expires_in_ms: Some(expires_in),
 	})
 }
 
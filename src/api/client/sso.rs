@@ -0,0 +1,273 @@
+use std::fmt::Write;
+
+use axum::{
+	extract::State,
+	response::{Html, IntoResponse, Redirect},
+};
+use axum_client_ip::InsecureClientIp;
+use conduwuit::{
+	err, info,
+	utils::{self, HtmlEscape},
+	Err, Result,
+};
+use http::{header::COOKIE, HeaderMap, Uri};
+use ruma::{
+	api::client::session::{sso_login, sso_login_with_provider},
+	events::{
+		push_rules::{PushRulesEvent, PushRulesEventContent},
+		room::message::RoomMessageEventContent,
+		GlobalAccountDataEventType,
+	},
+	push, OwnedUserId, UserId,
+};
+use serde::Deserialize;
+use service::{
+	sso::{Identity, CONFIRM_PATH, LOGIN_TIMEOUT, SESSION_COOKIE},
+	Services,
+};
+
+use super::TOKEN_LENGTH;
+use crate::Ruma;
+
+#[derive(Deserialize)]
+struct CallbackParams {
+	state: Option<String>,
+	code: Option<String>,
+	error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ConfirmParams {
+	id: String,
+}
+
+/// # `GET /_matrix/client/v3/login/sso/redirect`
+///
+/// Redirects the user to the first configured identity provider to log in.
+pub(crate) async fn sso_login_route(
+	State(services): State<crate::State>,
+	body: Ruma<sso_login::v3::Request>,
+) -> Result<sso_login::v3::Response> {
+	let (location, session) = services.sso.start_login(None, &body.redirect_url).await?;
+
+	Ok(sso_login::v3::Response {
+		location: location.into(),
+		cookie: Some(set_session_cookie(&session)),
+	})
+}
+
+/// # `GET /_matrix/client/v3/login/sso/redirect/{idpId}`
+///
+/// Redirects the user to the given identity provider to log in.
+pub(crate) async fn sso_login_with_provider_route(
+	State(services): State<crate::State>,
+	body: Ruma<sso_login_with_provider::v3::Request>,
+) -> Result<sso_login_with_provider::v3::Response> {
+	let (location, session) = services
+		.sso
+		.start_login(Some(&body.idp_id), &body.redirect_url)
+		.await?;
+
+	Ok(sso_login_with_provider::v3::Response {
+		location: location.into(),
+		cookie: Some(set_session_cookie(&session)),
+	})
+}
+
+/// # `GET /_conduwuit/sso/callback`
+///
+/// Where identity providers send users back to after they logged in. Maps the
+/// identity to a local user, registering one if needed, and asks the user to
+/// confirm the client they are about to be sent to.
+#[tracing::instrument(skip_all, fields(%client), name = "sso")]
+pub(crate) async fn sso_callback_route(
+	State(services): State<crate::State>,
+	InsecureClientIp(client): InsecureClientIp,
+	headers: HeaderMap,
+	uri: Uri,
+) -> Result<impl IntoResponse> {
+	let params: CallbackParams = serde_html_form::from_str(uri.query().unwrap_or_default())
+		.map_err(|e| err!(Request(InvalidParam("Invalid callback parameters: {e}"))))?;
+
+	if let Some(error) = params.error {
+		return Err!(Request(Forbidden("The identity provider refused the login: {error}")));
+	}
+
+	let (Some(state), Some(code)) = (params.state, params.code) else {
+		return Err!(Request(MissingParam("Missing state or code parameter.")));
+	};
+
+	let session = session_cookie(&headers);
+	let identity = services.sso.complete_login(&state, &code, session).await?;
+	let user_id = match services
+		.sso
+		.user_for_subject(&identity.idp_id, &identity.subject)
+		.await
+	{
+		| Ok(user_id) => user_id,
+		| Err(_) => register_sso_user(&services, &identity).await?,
+	};
+
+	if services.users.is_deactivated(&user_id).await? {
+		return Err!(Request(UserDeactivated("The user has been deactivated")));
+	}
+
+	info!("{user_id} logged in through identity provider {}", identity.idp_id);
+
+	let redirect_url = identity.redirect_url.clone();
+	let host = redirect_url
+		.host_str()
+		.unwrap_or_else(|| redirect_url.scheme());
+
+	let id = services.sso.await_confirmation(user_id.clone(), identity);
+
+	let mut confirm_url = services
+		.globals
+		.client_url()?
+		.join(CONFIRM_PATH)
+		.map_err(|e| err!("Failed to build the SSO confirmation URL: {e}"))?;
+
+	confirm_url.query_pairs_mut().append_pair("id", &id);
+
+	Ok(Html(format!(
+		"<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Continue to \
+		 {host}</title></head><body><p>You are signed in as {user_id}. Continue to \
+		 <strong>{host}</strong> to finish logging in?</p><p>Only continue if you were \
+		 logging in to an application at this address; it will get access to your \
+		 account.</p><p><a href=\"{confirm_url}\">Continue to {host}</a></p></body></html>",
+		host = HtmlEscape(host),
+		user_id = HtmlEscape(user_id.as_str()),
+		confirm_url = HtmlEscape(confirm_url.as_str()),
+	)))
+}
+
+/// # `GET /_conduwuit/sso/confirm`
+///
+/// Where users confirm the client they are sent to after logging in. Sends the
+/// user on to the client with a login token for `m.login.token`.
+#[tracing::instrument(skip_all, fields(%client), name = "sso")]
+pub(crate) async fn sso_confirm_route(
+	State(services): State<crate::State>,
+	InsecureClientIp(client): InsecureClientIp,
+	headers: HeaderMap,
+	uri: Uri,
+) -> Result<impl IntoResponse> {
+	let params: ConfirmParams = serde_html_form::from_str(uri.query().unwrap_or_default())
+		.map_err(|e| err!(Request(InvalidParam("Invalid confirmation parameters: {e}"))))?;
+
+	let (user_id, mut redirect_url) = services
+		.sso
+		.confirm_login(&params.id, session_cookie(&headers))?;
+
+	let login_token = utils::random_string(TOKEN_LENGTH);
+	let _expires_in = services.users.create_login_token(&user_id, &login_token);
+
+	redirect_url
+		.query_pairs_mut()
+		.append_pair("loginToken", &login_token);
+
+	Ok(Redirect::to(redirect_url.as_str()))
+}
+
+/// The `Set-Cookie` header storing a login session in the user's browser.
+fn set_session_cookie(session: &str) -> String {
+	format!(
+		"{SESSION_COOKIE}={session}; Path=/_conduwuit/sso; Max-Age={}; HttpOnly; Secure; \
+		 SameSite=Lax",
+		LOGIN_TIMEOUT.as_secs()
+	)
+}
+
+/// The login session stored in the user's browser, if any.
+fn session_cookie(headers: &HeaderMap) -> Option<&str> {
+	headers
+		.get_all(COOKIE)
+		.iter()
+		.filter_map(|header| header.to_str().ok())
+		.flat_map(|header| header.split(';'))
+		.filter_map(|cookie| cookie.trim().split_once('='))
+		.find(|(name, _)| *name == SESSION_COOKIE)
+		.map(|(_, session)| session)
+}
+
+/// Creates a local account for an identity which isn't mapped to one yet.
+async fn register_sso_user(services: &Services, identity: &Identity) -> Result<OwnedUserId> {
+	if !identity.allow_registration {
+		return Err!(Request(Forbidden("No account is linked to this identity.")));
+	}
+
+	let Some(localpart) = identity.localpart.as_deref() else {
+		return Err!(Request(Forbidden("The identity provider did not supply a username.")));
+	};
+
+	let user_id =
+		UserId::parse_with_server_name(localpart.to_lowercase(), services.globals.server_name())
+			.ok()
+			.filter(|user_id| !user_id.is_historical())
+			.ok_or_else(|| err!(Request(InvalidUsername("Username is invalid."))))?;
+
+	if services.users.exists(&user_id).await {
+		return Err!(Request(UserInUse(
+			"Username is already taken by an account not linked to this identity."
+		)));
+	}
+
+	if services
+		.globals
+		.forbidden_usernames()
+		.is_match(user_id.localpart())
+	{
+		return Err!(Request(Unknown("Username is forbidden.")));
+	}
+
+	// The account can only be logged into through the identity provider
+	services
+		.users
+		.create(&user_id, Some(&utils::random_string(TOKEN_LENGTH)))?;
+
+	let mut displayname = identity
+		.displayname
+		.clone()
+		.unwrap_or_else(|| user_id.localpart().to_owned());
+
+	if !services.globals.new_user_displayname_suffix().is_empty() {
+		write!(displayname, " {}", services.server.config.new_user_displayname_suffix)
+			.expect("should be able to write to string buffer");
+	}
+
+	services.users.set_displayname(&user_id, Some(displayname));
+
+	services
+		.account_data
+		.update(
+			None,
+			&user_id,
+			GlobalAccountDataEventType::PushRules.to_string().into(),
+			&serde_json::to_value(PushRulesEvent {
+				content: PushRulesEventContent {
+					global: push::Ruleset::server_default(&user_id),
+				},
+			})
+			.expect("to json always works"),
+		)
+		.await?;
+
+	services
+		.sso
+		.set_user_for_subject(&identity.idp_id, &identity.subject, &user_id);
+
+	info!("New user \"{user_id}\" registered through identity provider {}", identity.idp_id);
+
+	if services.server.config.admin_room_notices {
+		services
+			.admin
+			.send_message(RoomMessageEventContent::notice_plain(format!(
+				"New user \"{user_id}\" registered on this server through identity provider {}",
+				identity.idp_id
+			)))
+			.await
+			.ok();
+	}
+
+	Ok(user_id)
+}
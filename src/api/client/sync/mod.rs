@@ -11,6 +11,7 @@
 };
 use futures::{pin_mut, StreamExt};
 use ruma::{
+	api::client::filter::RoomEventFilter,
 	directory::RoomTypeFilter,
 	events::TimelineEventType::{
 		self, Beacon, CallInvite, PollStart, RoomEncrypted, RoomMessage, Sticker,
@@ -26,6 +27,10 @@
 pub(crate) const DEFAULT_BUMP_TYPES: &[TimelineEventType; 6] =
 	&[CallInvite, PollStart, Beacon, RoomEncrypted, RoomMessage, Sticker];
 
+/// With a timeline filter, how many events are looked at per event returned
+/// before the timeline is cut short and marked as limited.
+const FILTERED_TIMELINE_SCAN_FACTOR: usize = 10;
+
 async fn load_timeline(
 	services: &Services,
 	sender_user: &UserId,
@@ -33,6 +38,7 @@ async fn load_timeline(
 	roomsincecount: PduCount,
 	next_batch: Option<PduCount>,
 	limit: usize,
+	filter: Option<&RoomEventFilter>,
 ) -> Result<(Vec<(PduCount, PduEvent)>, bool), Error> {
 	let last_timeline_count = services
 		.rooms
@@ -44,23 +50,43 @@ async fn load_timeline(
 		return Ok((Vec::new(), false));
 	}
 
+	let scan_limit =
+		filter.map_or(usize::MAX, |_| limit.saturating_mul(FILTERED_TIMELINE_SCAN_FACTOR));
+
 	let non_timeline_pdus = services
 		.rooms
 		.timeline
 		.pdus_rev(Some(sender_user), room_id, None)
 		.ignore_err()
 		.ready_skip_while(|&(pducount, _)| pducount > next_batch.unwrap_or_else(PduCount::max))
-		.ready_take_while(|&(pducount, _)| pducount > roomsincecount);
+		.ready_take_while(|&(pducount, _)| pducount > roomsincecount)
+		.enumerate();
 
-	// Take the last events for the timeline
+	// Take the last events for the timeline. They /sync response doesn't always
+	// return all messages, so we say the output is limited if there are more
+	// matching events, or if we gave up looking for them.
 	pin_mut!(non_timeline_pdus);
-	let timeline_pdus: Vec<_> = non_timeline_pdus.by_ref().take(limit).collect().await;
-
-	let timeline_pdus: Vec<_> = timeline_pdus.into_iter().rev().collect();
+	let mut timeline_pdus = Vec::new();
+	let mut limited = false;
+	while let Some((scanned, (pducount, pdu))) = non_timeline_pdus.next().await {
+		if scanned >= scan_limit {
+			limited = true;
+			break;
+		}
+
+		if filter.is_some_and(|filter| !pdu.matches(filter)) {
+			continue;
+		}
+
+		if timeline_pdus.len() >= limit {
+			limited = true;
+			break;
+		}
+
+		timeline_pdus.push((pducount, pdu));
+	}
 
-	// They /sync response doesn't always return all messages, so we say the output
-	// is limited unless there are events in non_timeline_pdus
-	let limited = non_timeline_pdus.next().await.is_some();
+	timeline_pdus.reverse();
 
 	Ok((timeline_pdus, limited))
 }
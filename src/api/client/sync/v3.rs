@@ -12,7 +12,7 @@
 	result::FlatOk,
 	utils::{
 		self,
-		math::ruma_from_u64,
+		math::{ruma_from_u64, usize_from_ruma},
 		stream::{BroadbandExt, Tools, TryExpect, WidebandExt},
 		BoolExt, IterStream, ReadyExt, TryFutureExtExt,
 	},
@@ -32,7 +32,7 @@
 };
 use ruma::{
 	api::client::{
-		filter::FilterDefinition,
+		filter::{FilterDefinition, RoomFilter},
 		sync::sync_events::{
 			self,
 			v3::{
@@ -56,7 +56,13 @@
 use service::rooms::short::{ShortEventId, ShortStateKey};
 
 use super::{load_timeline, share_encrypted_room};
-use crate::{client::ignored_filter, Ruma, RumaResponse};
+use crate::{
+	client::{ignored_filter, visibility_filter},
+	Ruma, RumaResponse,
+};
+
+const TIMELINE_LIMIT_DEFAULT: usize = 10;
+const TIMELINE_LIMIT_MAX: usize = 100;
 
 #[derive(Default)]
 struct StateChanges {
@@ -145,6 +151,10 @@ pub(crate) async fn sync_events_route(
 	// Stop hanging if new info arrives
 	let default = Duration::from_secs(30);
 	let duration = cmp::min(body.body.timeout.unwrap_or(default), default);
+	if duration.is_zero() {
+		return Ok(response);
+	}
+
 	_ = tokio::time::timeout(duration, watcher).await;
 
 	// Retry returning data
@@ -180,6 +190,7 @@ pub(crate) async fn build_sync_events(
 		.rooms
 		.state_cache
 		.rooms_joined(sender_user)
+		.ready_filter(|room_id| room_filter(&filter.room, room_id))
 		.map(ToOwned::to_owned)
 		.broad_filter_map(|room_id| {
 			load_joined_room(
@@ -213,6 +224,7 @@ pub(crate) async fn build_sync_events(
 		.rooms
 		.state_cache
 		.rooms_left(sender_user)
+		.ready_filter(|(room_id, _)| room_filter(&filter.room, room_id))
 		.broad_filter_map(|(room_id, _)| {
 			handle_left_room(
 				services,
@@ -233,6 +245,7 @@ pub(crate) async fn build_sync_events(
 		.rooms
 		.state_cache
 		.rooms_invited(sender_user)
+		.ready_filter(|(room_id, _)| room_filter(&filter.room, room_id))
 		.fold_default(|mut invited_rooms: BTreeMap<_, _>, (room_id, invite_state)| async move {
 			let invite_count = services
 				.rooms
@@ -258,6 +271,7 @@ pub(crate) async fn build_sync_events(
 		.rooms
 		.state_cache
 		.rooms_knocked(sender_user)
+		.ready_filter(|(room_id, _)| room_filter(&filter.room, room_id))
 		.fold_default(|mut knocked_rooms: BTreeMap<_, _>, (room_id, knock_state)| async move {
 			let knock_count = services
 				.rooms
@@ -593,13 +607,21 @@ async fn load_joined_room(
 		.ok()
 		.map(Ok);
 
+	let timeline_limit = filter
+		.room
+		.timeline
+		.limit
+		.map_or(TIMELINE_LIMIT_DEFAULT, usize_from_ruma)
+		.min(TIMELINE_LIMIT_MAX);
+
 	let timeline = load_timeline(
 		services,
 		sender_user,
 		room_id,
 		sincecount,
 		Some(next_batchcount),
-		10_usize,
+		timeline_limit,
+		Some(&filter.room.timeline),
 	);
 
 	let receipt_events = services
@@ -726,6 +748,7 @@ async fn load_joined_room(
 		.iter()
 		.stream()
 		.wide_filter_map(|item| ignored_filter(services, item.clone(), sender_user))
+		.wide_filter_map(|item| visibility_filter(services, item, sender_user))
 		.map(|(_, pdu)| pdu.to_sync_room_event())
 		.collect();
 
@@ -753,6 +776,17 @@ async fn load_joined_room(
 		})
 		.into();
 
+	// Clients opting in get the counts of threads separately from the room's
+	let thread_notification_counts: OptionFuture<_> = (send_notification_counts
+		&& filter.room.timeline.unread_thread_notifications)
+		.then(|| {
+			services
+				.rooms
+				.user
+				.thread_notification_counts(sender_user, room_id)
+		})
+		.into();
+
 	let typing_events = services
 		.rooms
 		.typing
@@ -772,7 +806,8 @@ async fn load_joined_room(
 		})
 		.unwrap_or(Vec::new());
 
-	let unread_notifications = join(notification_count, highlight_count);
+	let unread_notifications =
+		join3(notification_count, highlight_count, thread_notification_counts);
 	let events = join3(room_events, account_data_events, typing_events);
 	let (unread_notifications, events, device_updates) =
 		join3(unread_notifications, events, device_updates)
@@ -780,7 +815,28 @@ async fn load_joined_room(
 			.await;
 
 	let (room_events, account_data_events, typing_events) = events;
-	let (notification_count, highlight_count) = unread_notifications;
+	let (notification_count, highlight_count, thread_notification_counts) = unread_notifications;
+	let thread_notification_counts = thread_notification_counts.unwrap_or_default();
+	let (notification_count, highlight_count) = thread_notification_counts.values().fold(
+		(notification_count, highlight_count),
+		|(notification_count, highlight_count), (notifications, highlights)| {
+			(
+				notification_count
+					.map(|count| count.saturating_sub(ruma_from_u64(*notifications))),
+				highlight_count.map(|count| count.saturating_sub(ruma_from_u64(*highlights))),
+			)
+		},
+	);
+
+	let unread_thread_notifications = thread_notification_counts
+		.into_iter()
+		.map(|(thread, (notifications, highlights))| {
+			(thread, UnreadNotificationsCount {
+				highlight_count: Some(ruma_from_u64(highlights)),
+				notification_count: Some(ruma_from_u64(notifications)),
+			})
+		})
+		.collect();
 
 	device_list_updates.extend(device_updates);
 
@@ -844,7 +900,7 @@ async fn load_joined_room(
 				.collect(),
 		},
 		ephemeral: Ephemeral { events: edus },
-		unread_thread_notifications: BTreeMap::new(),
+		unread_thread_notifications,
 	};
 
 	Ok((joined_room, device_list_updates, left_encrypted_users))
@@ -1236,3 +1292,15 @@ async fn fold_hero(
 	heroes.push(user_id.to_owned());
 	heroes
 }
+
+/// Whether the room passes the include/exclude lists of the room filter.
+fn room_filter(filter: &RoomFilter, room_id: &RoomId) -> bool {
+	if filter.not_rooms.iter().any(|not_room| not_room == room_id) {
+		return false;
+	}
+
+	filter
+		.rooms
+		.as_ref()
+		.is_none_or(|rooms| rooms.iter().any(|room| room == room_id))
+}
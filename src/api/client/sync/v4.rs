@@ -516,6 +516,7 @@ pub(crate) async fn sync_events_v4_route(
 				roomsincecount,
 				None,
 				*timeline_limit,
+				None,
 			)
 			.await
 			{
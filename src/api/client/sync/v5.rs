@@ -31,7 +31,7 @@
 
 use super::{filter_rooms, share_encrypted_room};
 use crate::{
-	client::{ignored_filter, sync::load_timeline, DEFAULT_BUMP_TYPES},
+	client::{ignored_filter, sync::load_timeline, visibility_filter, DEFAULT_BUMP_TYPES},
 	Ruma,
 };
 
@@ -374,6 +374,7 @@ async fn process_rooms(
 				roomsincecount,
 				Some(PduCount::from(next_batch)),
 				*timeline_limit,
+				None,
 			)
 			.await
 			{
@@ -478,6 +479,7 @@ async fn process_rooms(
 			.iter()
 			.stream()
 			.filter_map(|item| ignored_filter(&services, item.clone(), sender_user))
+			.filter_map(|item| visibility_filter(&services, item, sender_user))
 			.map(|(_, pdu)| pdu.to_sync_room_event())
 			.collect()
 			.await;
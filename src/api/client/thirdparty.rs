@@ -1,17 +1,63 @@
-use std::collections::BTreeMap;
+use std::collections::{btree_map::Entry, BTreeMap};
 
-use ruma::api::client::thirdparty::get_protocols;
+use axum::extract::State;
+use conduwuit::{debug_warn, Err, Result};
+use ruma::api::{
+	appservice::{thirdparty as appservice_thirdparty, Registration},
+	client::thirdparty::{
+		get_location_for_protocol, get_location_for_room_alias, get_protocol, get_protocols,
+		get_user_for_protocol, get_user_for_user_id,
+	},
+};
+use service::Services;
 
-use crate::{Result, Ruma, RumaResponse};
+use crate::{Ruma, RumaResponse};
 
-/// # `GET /_matrix/client/r0/thirdparty/protocols`
+/// # `GET /_matrix/client/v3/thirdparty/protocols`
 ///
-/// TODO: Fetches all metadata about protocols supported by the homeserver.
+/// Fetches all metadata about protocols supported by the appservices registered
+/// on this homeserver.
 pub(crate) async fn get_protocols_route(
+	State(services): State<crate::State>,
 	_body: Ruma<get_protocols::v3::Request>,
 ) -> Result<get_protocols::v3::Response> {
-	// TODO
-	Ok(get_protocols::v3::Response { protocols: BTreeMap::new() })
+	let mut protocols = BTreeMap::new();
+	for registration in protocol_registrations(&services, None).await {
+		for protocol in registration.protocols.iter().flatten() {
+			let Some(response) = services
+				.sending
+				.send_appservice_request(
+					registration.clone(),
+					appservice_thirdparty::get_protocol::v1::Request {
+						protocol: protocol.clone(),
+					},
+				)
+				.await
+				.inspect_err(|e| {
+					debug_warn!("Protocol query to appservice {} failed: {e}", registration.id);
+				})
+				.ok()
+				.flatten()
+			else {
+				continue;
+			};
+
+			// Bridges sharing a protocol each contribute their own instances
+			match protocols.entry(protocol.clone()) {
+				| Entry::Vacant(entry) => {
+					entry.insert(response.protocol);
+				},
+				| Entry::Occupied(mut entry) => {
+					entry
+						.get_mut()
+						.instances
+						.extend(response.protocol.instances);
+				},
+			}
+		}
+	}
+
+	Ok(get_protocols::v3::Response { protocols })
 }
 
 /// # `GET /_matrix/client/unstable/thirdparty/protocols`
@@ -19,7 +65,186 @@ pub(crate) async fn get_protocols_route(
 /// Same as `get_protocols_route`, except for some reason Element Android legacy
 /// calls this
 pub(crate) async fn get_protocols_route_unstable(
+	state: State<crate::State>,
 	body: Ruma<get_protocols::v3::Request>,
 ) -> Result<RumaResponse<get_protocols::v3::Response>> {
-	get_protocols_route(body).await.map(RumaResponse)
+	get_protocols_route(state, body).await.map(RumaResponse)
+}
+
+/// # `GET /_matrix/client/v3/thirdparty/protocol/{protocol}`
+///
+/// Fetches the metadata of a single protocol from the appservices providing it.
+pub(crate) async fn get_protocol_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_protocol::v3::Request>,
+) -> Result<get_protocol::v3::Response> {
+	let mut protocol = None;
+	for registration in protocol_registrations(&services, Some(&body.protocol)).await {
+		let Some(response) = services
+			.sending
+			.send_appservice_request(
+				registration.clone(),
+				appservice_thirdparty::get_protocol::v1::Request {
+					protocol: body.protocol.clone(),
+				},
+			)
+			.await
+			.inspect_err(|e| {
+				debug_warn!("Protocol query to appservice {} failed: {e}", registration.id);
+			})
+			.ok()
+			.flatten()
+		else {
+			continue;
+		};
+
+		match protocol.as_mut() {
+			| None => protocol = Some(response.protocol),
+			| Some(protocol) => protocol.instances.extend(response.protocol.instances),
+		}
+	}
+
+	let Some(protocol) = protocol else {
+		return Err!(Request(NotFound("Protocol is not supported by this homeserver.")));
+	};
+
+	Ok(get_protocol::v3::Response { protocol })
+}
+
+/// # `GET /_matrix/client/v3/thirdparty/location/{protocol}`
+///
+/// Looks up third-party locations matching the given fields through the
+/// appservices providing the protocol.
+pub(crate) async fn get_location_for_protocol_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_location_for_protocol::v3::Request>,
+) -> Result<get_location_for_protocol::v3::Response> {
+	let mut locations = Vec::new();
+	for registration in protocol_registrations(&services, Some(&body.protocol)).await {
+		let request = appservice_thirdparty::get_location_for_protocol::v1::Request {
+			protocol: body.protocol.clone(),
+			fields: body.fields.clone(),
+		};
+
+		match services
+			.sending
+			.send_appservice_request(registration.clone(), request)
+			.await
+		{
+			| Ok(Some(response)) => locations.extend(response.locations),
+			| Ok(None) => {},
+			| Err(e) => {
+				debug_warn!("Location query to appservice {} failed: {e}", registration.id);
+			},
+		}
+	}
+
+	Ok(get_location_for_protocol::v3::Response { locations })
+}
+
+/// # `GET /_matrix/client/v3/thirdparty/location`
+///
+/// Looks up the third-party locations bridged to a Matrix room alias.
+pub(crate) async fn get_location_for_room_alias_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_location_for_room_alias::v3::Request>,
+) -> Result<get_location_for_room_alias::v3::Response> {
+	let mut locations = Vec::new();
+	for registration in protocol_registrations(&services, None).await {
+		let request = appservice_thirdparty::get_location_for_room_alias::v1::Request {
+			alias: body.alias.clone(),
+		};
+
+		match services
+			.sending
+			.send_appservice_request(registration.clone(), request)
+			.await
+		{
+			| Ok(Some(response)) => locations.extend(response.locations),
+			| Ok(None) => {},
+			| Err(e) => {
+				debug_warn!("Location query to appservice {} failed: {e}", registration.id);
+			},
+		}
+	}
+
+	Ok(get_location_for_room_alias::v3::Response { locations })
+}
+
+/// # `GET /_matrix/client/v3/thirdparty/user/{protocol}`
+///
+/// Looks up third-party users matching the given fields through the
+/// appservices providing the protocol.
+pub(crate) async fn get_user_for_protocol_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_user_for_protocol::v3::Request>,
+) -> Result<get_user_for_protocol::v3::Response> {
+	let mut users = Vec::new();
+	for registration in protocol_registrations(&services, Some(&body.protocol)).await {
+		let request = appservice_thirdparty::get_user_for_protocol::v1::Request {
+			protocol: body.protocol.clone(),
+			fields: body.fields.clone(),
+		};
+
+		match services
+			.sending
+			.send_appservice_request(registration.clone(), request)
+			.await
+		{
+			| Ok(Some(response)) => users.extend(response.users),
+			| Ok(None) => {},
+			| Err(e) => debug_warn!("User query to appservice {} failed: {e}", registration.id),
+		}
+	}
+
+	Ok(get_user_for_protocol::v3::Response { users })
+}
+
+/// # `GET /_matrix/client/v3/thirdparty/user`
+///
+/// Looks up the third-party users bridged to a Matrix user ID.
+pub(crate) async fn get_user_for_user_id_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_user_for_user_id::v3::Request>,
+) -> Result<get_user_for_user_id::v3::Response> {
+	let mut users = Vec::new();
+	for registration in protocol_registrations(&services, None).await {
+		let request = appservice_thirdparty::get_user_for_user_id::v1::Request {
+			userid: body.userid.clone(),
+		};
+
+		match services
+			.sending
+			.send_appservice_request(registration.clone(), request)
+			.await
+		{
+			| Ok(Some(response)) => users.extend(response.users),
+			| Ok(None) => {},
+			| Err(e) => debug_warn!("User query to appservice {} failed: {e}", registration.id),
+		}
+	}
+
+	Ok(get_user_for_user_id::v3::Response { users })
+}
+
+/// Registrations of the appservices providing `protocol`, or any third-party
+/// protocol at all when none is given.
+async fn protocol_registrations(
+	services: &Services,
+	protocol: Option<&str>,
+) -> Vec<Registration> {
+	services
+		.appservice
+		.read()
+		.await
+		.values()
+		.map(|appservice| &appservice.registration)
+		.filter(|registration| {
+			registration.protocols.as_ref().is_some_and(|protocols| {
+				!protocols.is_empty()
+					&& protocol.is_none_or(|protocol| protocols.iter().any(|p| p == protocol))
+			})
+		})
+		.cloned()
+		.collect()
 }
@@ -9,6 +9,7 @@
 		federation::{self, transactions::edu::DirectDeviceContent},
 	},
 	to_device::DeviceIdOrAllDevices,
+	OwnedServerName,
 };
 use service::sending::EduBuf;
 
@@ -34,34 +35,19 @@ pub(crate) async fn send_event_to_device_route(
 		return Ok(send_event_to_device::v3::Response {});
 	}
 
+	// messages for remote users are batched into one EDU per destination
+	let mut remote_messages: BTreeMap<OwnedServerName, BTreeMap<_, _>> = BTreeMap::new();
 	for (target_user_id, map) in &body.messages {
-		for (target_device_id_maybe, event) in map {
-			if !services.globals.user_is_local(target_user_id) {
-				let mut map = BTreeMap::new();
-				map.insert(target_device_id_maybe.clone(), event.clone());
-				let mut messages = BTreeMap::new();
-				messages.insert(target_user_id.clone(), map);
-				let count = services.globals.next_count()?;
-
-				let mut buf = EduBuf::new();
-				serde_json::to_writer(
-					&mut buf,
-					&federation::transactions::edu::Edu::DirectToDevice(DirectDeviceContent {
-						sender: sender_user.clone(),
-						ev_type: body.event_type.clone(),
-						message_id: count.to_string().into(),
-						messages,
-					}),
-				)
-				.expect("DirectToDevice EDU can be serialized");
-
-				services
-					.sending
-					.send_edu_server(target_user_id.server_name(), buf)?;
+		if !services.globals.user_is_local(target_user_id) {
+			remote_messages
+				.entry(target_user_id.server_name().to_owned())
+				.or_default()
+				.insert(target_user_id.clone(), map.clone());
 
-				continue;
-			}
+			continue;
+		}
 
+		for (target_device_id_maybe, event) in map {
 			let event_type = &body.event_type.to_string();
 
 			let event = event
@@ -102,6 +88,24 @@ pub(crate) async fn send_event_to_device_route(
 		}
 	}
 
+	for (server_name, messages) in remote_messages {
+		let count = services.globals.next_count()?;
+
+		let mut buf = EduBuf::new();
+		serde_json::to_writer(
+			&mut buf,
+			&federation::transactions::edu::Edu::DirectToDevice(DirectDeviceContent {
+				sender: sender_user.clone(),
+				ev_type: body.event_type.clone(),
+				message_id: count.to_string().into(),
+				messages,
+			}),
+		)
+		.expect("DirectToDevice EDU can be serialized");
+
+		services.sending.send_edu_server(&server_name, buf)?;
+	}
+
 	// Save transaction id with empty data
 	services
 		.transaction_ids
@@ -290,6 +290,19 @@ pub(crate) async fn set_profile_key_route(
 		return Err!(Request(BadJson("Key names cannot be longer than 128 bytes")));
 	}
 
+	let config = &services.server.config;
+	let can_change = match body.key.as_str() {
+		| "displayname" => config.allow_displayname_change,
+		| "avatar_url" => config.allow_avatar_change,
+		| _ => true,
+	};
+
+	if !can_change && body.appservice_info.is_none() {
+		return Err!(Request(Forbidden(
+			"Changing this profile field is disabled on this homeserver."
+		)));
+	}
+
 	if body.key == "displayname" {
 		let all_joined_rooms: Vec<OwnedRoomId> = services
 			.rooms
@@ -15,7 +15,11 @@
 
 /// # `GET /_matrix/client/r0/voip/turnServer`
 ///
-/// TODO: Returns information about the recommended turn server.
+/// Returns the configured TURN server URIs along with credentials for them.
+///
+/// - With `turn_secret`, time-limited credentials are derived from it using
+///   HMAC-SHA1, as expected by coturn's `use-auth-secret` mode
+/// - Otherwise the static `turn_username` and `turn_password` are returned
 pub(crate) async fn turn_server_route(
 	State(services): State<crate::State>,
 	body: Ruma<get_turn_server_info::v3::Request>,
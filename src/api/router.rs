@@ -35,6 +35,11 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		.ruma_route(&client::get_login_types_route)
 		.ruma_route(&client::login_route)
 		.ruma_route(&client::login_token_route)
+		.ruma_route(&client::sso_login_route)
+		.ruma_route(&client::sso_login_with_provider_route)
+		.route("/_conduwuit/sso/callback", get(client::sso_callback_route))
+		.route("/_conduwuit/sso/confirm", get(client::sso_confirm_route))
+		.ruma_route(&client::refresh_token_route)
 		.ruma_route(&client::whoami_route)
 		.ruma_route(&client::logout_route)
 		.ruma_route(&client::logout_all_route)
@@ -43,6 +48,9 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		.ruma_route(&client::third_party_route)
 		.ruma_route(&client::request_3pid_management_token_via_email_route)
 		.ruma_route(&client::request_3pid_management_token_via_msisdn_route)
+		.ruma_route(&client::request_registration_token_via_email_route)
+		.ruma_route(&client::request_password_change_token_via_email_route)
+		.route("/_conduwuit/email/validate", get(client::validate_email_route))
 		.ruma_route(&client::check_registration_token_validity)
 		.ruma_route(&client::get_capabilities_route)
 		.ruma_route(&client::get_pushrules_all_route)
@@ -73,6 +81,10 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		.ruma_route(&client::upload_keys_route)
 		.ruma_route(&client::get_keys_route)
 		.ruma_route(&client::claim_keys_route)
+		.ruma_route(&client::put_dehydrated_device_route)
+		.ruma_route(&client::get_dehydrated_device_route)
+		.ruma_route(&client::delete_dehydrated_device_route)
+		.ruma_route(&client::get_dehydrated_events_route)
 		.ruma_route(&client::create_backup_version_route)
 		.ruma_route(&client::update_backup_version_route)
 		.ruma_route(&client::delete_backup_version_route)
@@ -115,6 +127,11 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		.ruma_route(&client::search_users_route)
 		.ruma_route(&client::get_member_events_route)
 		.ruma_route(&client::get_protocols_route)
+		.ruma_route(&client::get_protocol_route)
+		.ruma_route(&client::get_location_for_protocol_route)
+		.ruma_route(&client::get_location_for_room_alias_route)
+		.ruma_route(&client::get_user_for_protocol_route)
+		.ruma_route(&client::get_user_for_user_id_route)
 		.route("/_matrix/client/unstable/thirdparty/protocols",
 			get(client::get_protocols_route_unstable))
 		.ruma_route(&client::send_message_event_route)
@@ -197,6 +214,8 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 				"/_matrix/key/v2/server/:key_id",
 				get(server::get_server_keys_deprecated_route),
 			)
+			.ruma_route(&server::get_remote_server_keys_route)
+			.ruma_route(&server::get_remote_server_keys_batch_route)
 			.ruma_route(&server::get_public_rooms_route)
 			.ruma_route(&server::get_public_rooms_filtered_route)
 			.ruma_route(&server::send_transaction_message_route)
@@ -215,6 +234,7 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 			.ruma_route(&server::create_join_event_v1_route)
 			.ruma_route(&server::create_join_event_v2_route)
 			.ruma_route(&server::create_invite_route)
+			.ruma_route(&server::exchange_third_party_invite_route)
 			.ruma_route(&server::get_devices_route)
 			.ruma_route(&server::get_room_information_route)
 			.ruma_route(&server::get_profile_information_route)
@@ -9,7 +9,7 @@
 	api::{
 		client::{
 			directory::get_public_rooms,
-			error::ErrorKind,
+			error::{ErrorKind, RetryAfter},
 			profile::{
 				get_avatar_url, get_display_name, get_profile, get_profile_key, get_timezone_key,
 			},
@@ -32,6 +32,7 @@
 enum Token {
 	Appservice(Box<RegistrationInfo>),
 	User((OwnedUserId, OwnedDeviceId)),
+	Expired,
 	Invalid,
 	None,
 }
@@ -59,7 +60,11 @@ pub(super) async fn auth(
 		if let Some(reg_info) = services.appservice.find_from_token(token).await {
 			Token::Appservice(Box::new(reg_info))
 		} else if let Ok((user_id, device_id)) = services.users.find_from_token(token).await {
-			Token::User((user_id, device_id))
+			if services.users.is_token_expired(token).await {
+				Token::Expired
+			} else {
+				Token::User((user_id, device_id))
+			}
 		} else {
 			Token::Invalid
 		}
@@ -80,7 +85,7 @@ pub(super) async fn auth(
 							// we should have validated the token above
 							// already
 						},
-						| Token::None | Token::Invalid => {
+						| Token::None | Token::Expired | Token::Invalid => {
 							return Err(Error::BadRequest(
 								ErrorKind::MissingToken,
 								"Missing or invalid access token.",
@@ -100,7 +105,7 @@ pub(super) async fn auth(
 							// we should have validated the token above
 							// already
 						},
-						| Token::None | Token::Invalid => {
+						| Token::None | Token::Expired | Token::Invalid => {
 							return Err(Error::BadRequest(
 								ErrorKind::MissingToken,
 								"Missing or invalid access token.",
@@ -189,6 +194,16 @@ pub(super) async fn auth(
 				))
 			}
 		},
+		| (AuthScheme::None, Token::Expired) => Ok(Auth {
+			origin: None,
+			sender_user: None,
+			sender_device: None,
+			appservice_info: None,
+		}),
+		| (_, Token::Expired) => Err(Error::BadRequest(
+			ErrorKind::UnknownToken { soft_logout: true },
+			"Access token has expired.",
+		)),
 		| (_, Token::Invalid) => Err(Error::BadRequest(
 			ErrorKind::UnknownToken { soft_logout: false },
 			"Unknown access token.",
@@ -298,6 +313,15 @@ async fn auth_server(
 		return Err!(Request(Forbidden("Failed to verify X-Matrix signatures.")));
 	}
 
+	if let Some(retry_after) = services.globals.federation_ratelimit(origin) {
+		return Err(Error::BadRequest(
+			ErrorKind::LimitExceeded {
+				retry_after: Some(RetryAfter::Delay(retry_after)),
+			},
+			"Too many federation requests.",
+		));
+	}
+
 	Ok(Auth {
 		origin: origin.to_owned().into(),
 		sender_user: None,
@@ -311,9 +335,15 @@ fn auth_server_checks(services: &Services, x_matrix: &XMatrix) -> Result<()> {
 		return Err!(Config("allow_federation", "Federation is disabled."));
 	}
 
+	// Older servers may omit the destination; it is still covered by the signature
+	// since we always verify against our own server name.
 	let destination = services.globals.server_name();
-	if x_matrix.destination.as_deref() != Some(destination) {
-		return Err!(Request(Forbidden("Invalid destination.")));
+	if x_matrix
+		.destination
+		.as_deref()
+		.is_some_and(|x_destination| x_destination != destination)
+	{
+		return Err!(Request(Unauthorized("Invalid destination.")));
 	}
 
 	let origin = &x_matrix.origin;
@@ -328,6 +358,13 @@ fn auth_server_checks(services: &Services, x_matrix: &XMatrix) -> Result<()> {
 		))));
 	}
 
+	let allowed = &services.server.config.allowed_remote_server_names;
+	if !allowed.is_empty() && !allowed.contains(origin) {
+		return Err!(Request(Forbidden(debug_warn!(
+			"Federation requests from {origin} are not in the allowed list."
+		))));
+	}
+
 	Ok(())
 }
 
@@ -1,12 +1,16 @@
-use std::cmp;
+use std::collections::{BinaryHeap, HashSet};
 
 use axum::extract::State;
 use conduwuit::{
-	utils::{stream::TryTools, IterStream, ReadyExt},
-	PduCount, Result,
+	utils::{future::TryExtExt, stream::BroadbandExt, IterStream, ReadyExt},
+	PduEvent, Result,
 };
-use futures::{FutureExt, StreamExt, TryStreamExt};
-use ruma::{api::federation::backfill::get_backfill, uint, MilliSecondsSinceUnixEpoch};
+use futures::StreamExt;
+use ruma::{
+	api::federation::backfill::get_backfill, uint, EventId, MilliSecondsSinceUnixEpoch,
+	OwnedEventId, RoomId, UInt,
+};
+use service::Services;
 
 use super::AccessCheck;
 use crate::Ruma;
@@ -15,6 +19,10 @@
 ///
 /// Retrieves events from before the sender joined the room, if the room's
 /// history visibility allows.
+///
+/// Walks the `prev_events` of the supplied extremities breadth-first, always
+/// continuing from the deepest event discovered so far, so the response is in
+/// reverse topological order.
 pub(crate) async fn get_backfill_route(
 	State(services): State<crate::State>,
 	ref body: Ruma<get_backfill::v1::Request>,
@@ -34,53 +42,73 @@ pub(crate) async fn get_backfill_route(
 		.try_into()
 		.expect("UInt could not be converted to usize");
 
-	let from = body
-		.v
-		.iter()
-		.stream()
-		.filter_map(|event_id| {
+	let mut seen: HashSet<OwnedEventId> = body.v.iter().cloned().collect();
+	let mut queue: BinaryHeap<(UInt, OwnedEventId)> = BinaryHeap::new();
+	queue.extend(depths(&services, &body.room_id, body.v.iter()).await);
+
+	let mut pdus = Vec::with_capacity(limit);
+	while pdus.len() < limit {
+		let Some((_, event_id)) = queue.pop() else {
+			break;
+		};
+
+		let Ok(pdu) = services.rooms.timeline.get_pdu(&event_id).await else {
+			continue;
+		};
+
+		let prev_events = pdu
+			.prev_events
+			.iter()
+			.filter(|prev_event| seen.insert((*prev_event).clone()));
+
+		queue.extend(depths(&services, &body.room_id, prev_events).await);
+
+		if !services
+			.rooms
+			.state_accessor
+			.server_can_see_event(body.origin(), &body.room_id, &event_id)
+			.await
+		{
+			continue;
+		}
+
+		let Ok(pdu_json) = services.rooms.timeline.get_pdu_json(&event_id).await else {
+			continue;
+		};
+
+		pdus.push(
 			services
-				.rooms
-				.timeline
-				.get_pdu_count(event_id)
-				.map(Result::ok)
-		})
-		.ready_fold(PduCount::min(), cmp::max)
-		.await;
+				.sending
+				.convert_to_outgoing_federation_event(pdu_json)
+				.await,
+		);
+	}
 
 	Ok(get_backfill::v1::Response {
 		origin_server_ts: MilliSecondsSinceUnixEpoch::now(),
 
 		origin: services.globals.server_name().to_owned(),
 
-		pdus: services
-			.rooms
-			.timeline
-			.pdus_rev(None, &body.room_id, Some(from.saturating_add(1)))
-			.try_take(limit)
-			.try_filter_map(|(_, pdu)| async move {
-				Ok(services
-					.rooms
-					.state_accessor
-					.server_can_see_event(body.origin(), &pdu.room_id, &pdu.event_id)
-					.await
-					.then_some(pdu))
-			})
-			.try_filter_map(|pdu| async move {
-				Ok(services
-					.rooms
-					.timeline
-					.get_pdu_json(&pdu.event_id)
-					.await
-					.ok())
-			})
-			.and_then(|pdu| {
-				services
-					.sending
-					.convert_to_outgoing_federation_event(pdu)
-					.map(Ok)
-			})
-			.try_collect()
-			.await?,
+		pdus,
 	})
 }
+
+/// Looks up the depth of each known event belonging to the room; unknown
+/// events and events from other rooms are dropped.
+async fn depths<'a, I>(
+	services: &Services,
+	room_id: &RoomId,
+	event_ids: I,
+) -> Vec<(UInt, OwnedEventId)>
+where
+	I: Iterator<Item = &'a OwnedEventId> + Send,
+{
+	event_ids
+		.map(AsRef::<EventId>::as_ref)
+		.stream()
+		.broad_filter_map(|event_id| services.rooms.timeline.get_pdu(event_id).ok())
+		.ready_filter(|pdu: &PduEvent| *pdu.room_id == *room_id)
+		.map(|pdu| (pdu.depth, pdu.event_id))
+		.collect()
+		.await
+}
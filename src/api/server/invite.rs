@@ -81,6 +81,35 @@ pub(crate) async fn create_invite_route(
 		.acl_check(invited_user.server_name(), &body.room_id)
 		.await?;
 
+	let sender: OwnedUserId = signed_event
+		.get("sender")
+		.try_into()
+		.map(UserId::to_owned)
+		.map_err(|e| err!(Request(InvalidParam("Invalid sender property: {e}"))))?;
+
+	if sender.server_name() != body.origin() {
+		return Err!(Request(Forbidden("Not allowed to invite on behalf of another server.")));
+	}
+
+	let content: RoomMemberEventContent = serde_json::from_value(
+		signed_event
+			.get("content")
+			.ok_or_else(|| err!(Request(BadJson("Event missing content property."))))?
+			.clone()
+			.into(),
+	)
+	.map_err(|e| err!(Request(BadJson(warn!("Event content is empty or invalid: {e}")))))?;
+
+	if content.membership != MembershipState::Invite {
+		return Err!(Request(BadJson("Not allowed to send a non-invite membership event.")));
+	}
+
+	services
+		.server_keys
+		.verify_event(&signed_event, Some(&body.room_version))
+		.await
+		.map_err(|e| err!(Request(Forbidden(warn!("Invite event failed verification: {e}")))))?;
+
 	services
 		.server_keys
 		.hash_and_sign_event(&mut signed_event, &body.room_version)
@@ -88,15 +117,16 @@ pub(crate) async fn create_invite_route(
 
 	// Generate event id
 	let event_id = gen_event_id(&signed_event, &body.room_version)?;
+	if event_id != body.event_id {
+		return Err!(Request(InvalidParam(
+			"Event ID {event_id} does not match the event ID {} in the request path.",
+			body.event_id
+		)));
+	}
 
 	// Add event_id back
 	signed_event.insert("event_id".to_owned(), CanonicalJsonValue::String(event_id.to_string()));
 
-	let sender: &UserId = signed_event
-		.get("sender")
-		.try_into()
-		.map_err(|e| err!(Request(InvalidParam("Invalid sender property: {e}"))))?;
-
 	if services.rooms.metadata.is_banned(&body.room_id).await
 		&& !services.users.is_admin(&invited_user).await
 	{
@@ -137,7 +167,7 @@ pub(crate) async fn create_invite_route(
 				&body.room_id,
 				&invited_user,
 				RoomMemberEventContent::new(MembershipState::Invite),
-				sender,
+				&sender,
 				Some(invite_state),
 				body.via.clone(),
 				true,
@@ -4,15 +4,32 @@
 };
 
 use axum::{extract::State, response::IntoResponse, Json};
-use conduwuit::{utils::timepoint_from_now, Result};
+use conduwuit::{
+	err,
+	utils::{self, future::TryExtExt, timepoint_from_now, IterStream},
+	Err, Result,
+};
+use futures::{future::ready, StreamExt};
 use ruma::{
 	api::{
-		federation::discovery::{get_server_keys, OldVerifyKey, ServerSigningKeys},
+		federation::discovery::{
+			get_remote_server_keys, get_remote_server_keys_batch, get_server_keys, OldVerifyKey,
+			ServerSigningKeys,
+		},
 		OutgoingResponse,
 	},
 	serde::Raw,
-	MilliSecondsSinceUnixEpoch, Signatures,
+	CanonicalJsonObject, MilliSecondsSinceUnixEpoch, ServerName, Signatures,
 };
+use service::Services;
+
+use crate::Ruma;
+
+/// How many servers' keys can be queried in one batch.
+const NOTARY_BATCH_LIMIT: usize = 64;
+
+/// How many servers' keys of a batch are fetched at once.
+const NOTARY_BATCH_CONCURRENCY: usize = 8;
 
 /// # `GET /_matrix/key/v2/server`
 ///
@@ -25,6 +42,11 @@
 pub(crate) async fn get_server_keys_route(
 	State(services): State<crate::State>,
 ) -> Result<impl IntoResponse> {
+	Ok(Json(local_server_keys(&services).await?))
+}
+
+/// Our own signing keys as a signed `ServerSigningKeys` object.
+async fn local_server_keys(services: &Services) -> Result<CanonicalJsonObject> {
 	let server_name = services.globals.server_name();
 	let active_key_id = services.server_keys.active_key_id();
 	let mut all_keys = services.server_keys.verify_keys_for(server_name).await;
@@ -54,7 +76,87 @@ pub(crate) async fn get_server_keys_route(
 
 	services.server_keys.sign_json(&mut response)?;
 
-	Ok(Json(response))
+	Ok(response)
+}
+
+/// # `GET /_matrix/key/v2/query/{serverName}`
+///
+/// Queries the public signing keys of another server through this server
+/// acting as a notary.
+pub(crate) async fn get_remote_server_keys_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_remote_server_keys::v2::Request>,
+) -> Result<get_remote_server_keys::v2::Response> {
+	let server_key =
+		notary_server_keys(&services, &body.server_name, body.minimum_valid_until_ts).await?;
+
+	Ok(get_remote_server_keys::v2::Response::new(vec![server_key]))
+}
+
+/// # `POST /_matrix/key/v2/query`
+///
+/// Queries the public signing keys of several servers through this server
+/// acting as a notary. Servers whose keys cannot be obtained are omitted.
+pub(crate) async fn get_remote_server_keys_batch_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_remote_server_keys_batch::v2::Request>,
+) -> Result<get_remote_server_keys_batch::v2::Response> {
+	if body.server_keys.len() > NOTARY_BATCH_LIMIT {
+		return Err!(Request(Forbidden(
+			"Not allowed to query the keys of more than {NOTARY_BATCH_LIMIT} servers at once"
+		)));
+	}
+
+	let server_keys = body
+		.server_keys
+		.iter()
+		.stream()
+		.map(|(server_name, criteria)| {
+			let minimum_valid_until_ts = criteria
+				.values()
+				.filter_map(|criteria| criteria.minimum_valid_until_ts)
+				.max()
+				.unwrap_or_else(MilliSecondsSinceUnixEpoch::now);
+
+			notary_server_keys(&services, server_name, minimum_valid_until_ts).ok()
+		})
+		.buffer_unordered(NOTARY_BATCH_CONCURRENCY)
+		.filter_map(ready)
+		.collect()
+		.await;
+
+	Ok(get_remote_server_keys_batch::v2::Response::new(server_keys))
+}
+
+/// Obtains the signing keys of the server with the origin's self-signature
+/// intact, from the cache if they are still valid, and countersigns them with
+/// our own key.
+async fn notary_server_keys(
+	services: &Services,
+	server_name: &ServerName,
+	minimum_valid_until_ts: MilliSecondsSinceUnixEpoch,
+) -> Result<Raw<ServerSigningKeys>> {
+	if services.globals.server_is_ours(server_name) {
+		return Ok(Raw::new(&local_server_keys(services).await?)?.cast());
+	}
+
+	let server_key = services
+		.server_keys
+		.notary_keys(server_name, minimum_valid_until_ts)
+		.await?;
+
+	if server_key.valid_until_ts < minimum_valid_until_ts {
+		return Err!(Request(NotFound(
+			"Keys for {server_name} are not valid until the requested time."
+		)));
+	}
+
+	let mut server_key = utils::to_canonical_object(&server_key)
+		.map_err(|e| err!(BadServerResponse("Invalid server keys for {server_name}: {e}")))?;
+
+	services.server_keys.sign_json(&mut server_key)?;
+
+	Ok(Raw::new(&server_key)?.cast())
 }
 
 fn valid_until_ts() -> MilliSecondsSinceUnixEpoch {
@@ -2,7 +2,13 @@
 use conduwuit::{debug_warn, Err};
 use ruma::{
 	api::{client::error::ErrorKind, federation::knock::create_knock_event_template},
-	events::room::member::{MembershipState, RoomMemberEventContent},
+	events::{
+		room::{
+			join_rules::{JoinRule, RoomJoinRulesEventContent},
+			member::{MembershipState, RoomMemberEventContent},
+		},
+		StateEventType,
+	},
 	RoomVersionId,
 };
 use serde_json::value::to_raw_value;
@@ -76,6 +82,21 @@ pub(crate) async fn create_knock_event_template_route(
 		));
 	}
 
+	let join_rule = services
+		.rooms
+		.state_accessor
+		.room_state_get_content::<RoomJoinRulesEventContent>(
+			&body.room_id,
+			&StateEventType::RoomJoinRules,
+			"",
+		)
+		.await
+		.map(|content| content.join_rule);
+
+	if !matches!(join_rule, Ok(JoinRule::Knock | JoinRule::KnockRestricted(_))) {
+		return Err!(Request(Forbidden("This room does not allow knocking.")));
+	}
+
 	let state_lock = services.rooms.state.mutex.lock(&body.room_id).await;
 
 	if let Ok(membership) = services
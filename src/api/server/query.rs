@@ -1,7 +1,7 @@
 use std::collections::BTreeMap;
 
 use axum::extract::State;
-use conduwuit::{err, Error, Result};
+use conduwuit::{err, Err, Error, Result};
 use futures::StreamExt;
 use get_profile_information::v1::ProfileField;
 use rand::seq::SliceRandom;
@@ -22,6 +22,13 @@ pub(crate) async fn get_room_information_route(
 	State(services): State<crate::State>,
 	body: Ruma<get_room_information::v1::Request>,
 ) -> Result<get_room_information::v1::Response> {
+	if !services
+		.globals
+		.server_is_ours(body.room_alias.server_name())
+	{
+		return Err!(Request(InvalidParam("Room alias does not belong to this server.")));
+	}
+
 	let room_id = services
 		.rooms
 		.alias
@@ -29,6 +36,12 @@ pub(crate) async fn get_room_information_route(
 		.await
 		.map_err(|_| err!(Request(NotFound("Room alias not found."))))?;
 
+	services
+		.rooms
+		.event_handler
+		.acl_check(body.origin(), &room_id)
+		.await?;
+
 	let mut servers: Vec<OwnedServerName> = services
 		.rooms
 		.state_cache
@@ -35,6 +35,7 @@
 };
 use service::{
 	sending::{EDU_LIMIT, PDU_LIMIT},
+	transaction_ids::server_txnid_key,
 	Services,
 };
 use utils::millis_since_unix_epoch;
@@ -82,6 +83,29 @@ pub(crate) async fn send_transaction_message_route(
 		)));
 	}
 
+	// Hold the transaction until its response is stored, so that a retry sent
+	// while it is still being processed replays the response instead of
+	// processing it again.
+	let txn_key = server_txnid_key(body.origin(), &body.transaction_id);
+	let _txn_lock = services
+		.transaction_ids
+		.mutex_server_txnid
+		.lock(&txn_key)
+		.await;
+
+	// Replay the response for a transaction we have already processed
+	if let Ok(response) = services
+		.transaction_ids
+		.existing_server_txnid(body.origin(), &body.transaction_id)
+		.await
+	{
+		debug!(id = ?body.transaction_id, origin = ?body.origin(), "Replaying txn response");
+		return Ok(send_transaction_message::v1::Response {
+			pdus: serde_json::from_slice(&response)
+				.map_err(|e| err!(Database("Invalid response in server txnid data: {e}")))?,
+		});
+	}
+
 	let txn_start_time = Instant::now();
 	trace!(
 		pdus = body.pdus.len(),
@@ -126,12 +150,17 @@ pub(crate) async fn send_transaction_message_route(
 		}
 	}
 
-	Ok(send_transaction_message::v1::Response {
-		pdus: results
-			.into_iter()
-			.map(|(e, r)| (e, r.map_err(error::sanitized_message)))
-			.collect(),
-	})
+	let pdus: BTreeMap<_, _> = results
+		.into_iter()
+		.map(|(e, r)| (e, r.map_err(error::sanitized_message)))
+		.collect();
+
+	services
+		.transaction_ids
+		.add_server_txnid(body.origin(), &body.transaction_id, &serde_json::to_vec(&pdus)?)
+		.await;
+
+	Ok(send_transaction_message::v1::Response { pdus })
 }
 
 async fn handle(
@@ -358,6 +387,14 @@ async fn handle_edu_receipt_room_user(
 		return;
 	}
 
+	if !services.rooms.state_cache.is_joined(user_id, room_id).await {
+		debug_warn!(
+			%user_id, %room_id, %origin,
+			"received read receipt EDU for user not in room"
+		);
+		return;
+	}
+
 	let data = &user_updates.data;
 	user_updates
 		.event_ids
@@ -585,12 +622,10 @@ async fn handle_edu_signing_key_update(
 		return;
 	}
 
-	if let Some(master_key) = master_key {
-		services
-			.users
-			.add_cross_signing_keys(&user_id, &master_key, &self_signing_key, &None, true)
-			.await
-			.log_err()
-			.ok();
-	}
+	services
+		.users
+		.add_cross_signing_keys(&user_id, master_key.as_ref(), &self_signing_key, &None, true)
+		.await
+		.log_err()
+		.ok();
 }
@@ -16,7 +16,7 @@
 		room::member::{MembershipState, RoomMemberEventContent},
 		StateEventType,
 	},
-	CanonicalJsonValue, OwnedEventId, OwnedRoomId, OwnedServerName, OwnedUserId, RoomId,
+	CanonicalJsonValue, EventId, OwnedEventId, OwnedRoomId, OwnedServerName, OwnedUserId, RoomId,
 	ServerName,
 };
 use serde_json::value::{to_raw_value, RawValue as RawJsonValue};
@@ -29,6 +29,7 @@ async fn create_join_event(
 	services: &Services,
 	origin: &ServerName,
 	room_id: &RoomId,
+	expected_event_id: &EventId,
 	pdu: &RawJsonValue,
 ) -> Result<create_join_event::v1::RoomState> {
 	if !services.rooms.metadata.exists(room_id).await {
@@ -60,6 +61,13 @@ async fn create_join_event(
 		return Err!(Request(BadJson("Could not convert event to canonical json.")));
 	};
 
+	if event_id != expected_event_id {
+		return Err!(Request(InvalidParam(
+			"Event ID {event_id} does not match the event ID {expected_event_id} in the request \
+			 path."
+		)));
+	}
+
 	let event_room_id: OwnedRoomId = serde_json::from_value(
 		value
 			.get("room_id")
@@ -301,9 +309,10 @@ pub(crate) async fn create_join_event_v1_route(
 		}
 	}
 
-	let room_state = create_join_event(&services, body.origin(), &body.room_id, &body.pdu)
-		.boxed()
-		.await?;
+	let room_state =
+		create_join_event(&services, body.origin(), &body.room_id, &body.event_id, &body.pdu)
+			.boxed()
+			.await?;
 
 	Ok(create_join_event::v1::Response { room_state })
 }
@@ -344,7 +353,7 @@ pub(crate) async fn create_join_event_v2_route(
 	}
 
 	let create_join_event::v1::RoomState { auth_chain, state, event } =
-		create_join_event(&services, body.origin(), &body.room_id, &body.pdu)
+		create_join_event(&services, body.origin(), &body.room_id, &body.event_id, &body.pdu)
 			.boxed()
 			.await?;
 	let room_state = create_join_event::v2::RoomState {
@@ -75,6 +75,13 @@ pub(crate) async fn create_knock_event_v1_route(
 		return Err!(Request(InvalidParam("Could not convert event to canonical json.")));
 	};
 
+	if event_id != body.event_id {
+		return Err!(Request(InvalidParam(
+			"Event ID {event_id} does not match the event ID {} in the request path.",
+			body.event_id
+		)));
+	}
+
 	let event_type: StateEventType = serde_json::from_value(
 		value
 			.get("type")
@@ -9,7 +9,7 @@
 		room::member::{MembershipState, RoomMemberEventContent},
 		StateEventType,
 	},
-	OwnedRoomId, OwnedUserId, RoomId, ServerName,
+	EventId, OwnedRoomId, OwnedUserId, RoomId, ServerName,
 };
 use serde_json::value::RawValue as RawJsonValue;
 
@@ -25,7 +25,8 @@ pub(crate) async fn create_leave_event_v1_route(
 	State(services): State<crate::State>,
 	body: Ruma<create_leave_event::v1::Request>,
 ) -> Result<create_leave_event::v1::Response> {
-	create_leave_event(&services, body.origin(), &body.room_id, &body.pdu).await?;
+	create_leave_event(&services, body.origin(), &body.room_id, &body.event_id, &body.pdu)
+		.await?;
 
 	Ok(create_leave_event::v1::Response::new())
 }
@@ -37,7 +38,8 @@ pub(crate) async fn create_leave_event_v2_route(
 	State(services): State<crate::State>,
 	body: Ruma<create_leave_event::v2::Request>,
 ) -> Result<create_leave_event::v2::Response> {
-	create_leave_event(&services, body.origin(), &body.room_id, &body.pdu).await?;
+	create_leave_event(&services, body.origin(), &body.room_id, &body.event_id, &body.pdu)
+		.await?;
 
 	Ok(create_leave_event::v2::Response::new())
 }
@@ -46,6 +48,7 @@ async fn create_leave_event(
 	services: &Services,
 	origin: &ServerName,
 	room_id: &RoomId,
+	expected_event_id: &EventId,
 	pdu: &RawJsonValue,
 ) -> Result {
 	if !services.rooms.metadata.exists(room_id).await {
@@ -67,6 +70,13 @@ async fn create_leave_event(
 		return Err!(Request(BadJson("Could not convert event to canonical json.")));
 	};
 
+	if event_id != expected_event_id {
+		return Err!(Request(InvalidParam(
+			"Event ID {event_id} does not match the event ID {expected_event_id} in the request \
+			 path."
+		)));
+	}
+
 	let event_room_id: OwnedRoomId = serde_json::from_value(
 		serde_json::to_value(
 			value
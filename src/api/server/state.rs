@@ -1,4 +1,4 @@
-use std::{borrow::Borrow, iter::once};
+use std::borrow::Borrow;
 
 use axum::extract::State;
 use conduwuit::{at, err, utils::IterStream, Result};
@@ -19,7 +19,7 @@ pub(crate) async fn get_room_state_route(
 		services: &services,
 		origin: body.origin(),
 		room_id: &body.room_id,
-		event_id: None,
+		event_id: Some(&body.event_id),
 	}
 	.check()
 	.await?;
@@ -55,7 +55,7 @@ pub(crate) async fn get_room_state_route(
 	let auth_chain = services
 		.rooms
 		.auth_chain
-		.event_ids_iter(&body.room_id, once(body.event_id.borrow()))
+		.event_ids_iter(&body.room_id, state_ids.iter().map(Borrow::borrow))
 		.and_then(|id| async move { services.rooms.timeline.get_pdu_json(&id).await })
 		.and_then(|pdu| {
 			services
@@ -1,4 +1,4 @@
-use std::{borrow::Borrow, iter::once};
+use std::borrow::Borrow;
 
 use axum::extract::State;
 use conduwuit::{at, err, Result};
@@ -20,7 +20,7 @@ pub(crate) async fn get_room_state_ids_route(
 		services: &services,
 		origin: body.origin(),
 		room_id: &body.room_id,
-		event_id: None,
+		event_id: Some(&body.event_id),
 	}
 	.check()
 	.await?;
@@ -43,7 +43,7 @@ pub(crate) async fn get_room_state_ids_route(
 	let auth_chain_ids = services
 		.rooms
 		.auth_chain
-		.event_ids_iter(&body.room_id, once(body.event_id.borrow()))
+		.event_ids_iter(&body.room_id, pdu_ids.iter().map(Borrow::borrow))
 		.try_collect()
 		.await?;
 
@@ -0,0 +1,128 @@
+use axum::extract::State;
+use conduwuit::{err, pdu::gen_event_id_canonical_json, warn, Err, Result};
+use futures::FutureExt;
+use ruma::{
+	api::federation::{membership::create_invite, third_party::exchange_invite},
+	events::{room::member::MembershipState, StateEventType},
+};
+
+use crate::{service::pdu::PduBuilder, Ruma};
+
+/// # `PUT /_matrix/federation/v1/exchange_third_party_invite/{roomId}`
+///
+/// Converts a third-party invite into an `m.room.member` invite for the user
+/// the identity server bound the third-party identifier to.
+pub(crate) async fn exchange_third_party_invite_route(
+	State(services): State<crate::State>,
+	body: Ruma<exchange_invite::v1::Request>,
+) -> Result<exchange_invite::v1::Response> {
+	if body.kind != StateEventType::RoomMember {
+		return Err!(Request(InvalidParam("Event type must be m.room.member.")));
+	}
+
+	if body.content.membership != MembershipState::Invite {
+		return Err!(Request(InvalidParam("Membership must be invite.")));
+	}
+
+	let Some(third_party_invite) = &body.content.third_party_invite else {
+		return Err!(Request(InvalidParam("Membership content has no third_party_invite.")));
+	};
+
+	if third_party_invite.signed.mxid != body.state_key {
+		return Err!(Request(InvalidParam("Signed mxid does not match the state_key.")));
+	}
+
+	if !services.globals.user_is_local(&body.sender) {
+		return Err!(Request(InvalidParam("Sender does not belong to this homeserver.")));
+	}
+
+	if body.state_key.server_name() != body.origin() {
+		return Err!(Request(Forbidden(
+			"Not allowed to exchange invites on behalf of another server."
+		)));
+	}
+
+	if !services.rooms.metadata.exists(&body.room_id).await {
+		return Err!(Request(NotFound("Room is unknown to this server.")));
+	}
+
+	services
+		.rooms
+		.event_handler
+		.acl_check(body.origin(), &body.room_id)
+		.await?;
+
+	let (pdu, pdu_json, invite_room_state) = {
+		let state_lock = services.rooms.state.mutex.lock(&body.room_id).await;
+
+		let (pdu, pdu_json) = services
+			.rooms
+			.timeline
+			.create_hash_and_sign_event(
+				PduBuilder::state(body.state_key.to_string(), &body.content),
+				&body.sender,
+				&body.room_id,
+				&state_lock,
+			)
+			.await?;
+
+		let invite_room_state = services.rooms.state.summary_stripped(&pdu).await;
+
+		drop(state_lock);
+
+		(pdu, pdu_json, invite_room_state)
+	};
+
+	let room_version_id = services.rooms.state.get_room_version(&body.room_id).await?;
+
+	let response = services
+		.sending
+		.send_federation_request(body.origin(), create_invite::v2::Request {
+			room_id: body.room_id.clone(),
+			event_id: pdu.event_id.clone(),
+			room_version: room_version_id.clone(),
+			event: services
+				.sending
+				.convert_to_outgoing_federation_event(pdu_json)
+				.await,
+			invite_room_state,
+			via: services
+				.rooms
+				.state_cache
+				.servers_route_via(&body.room_id)
+				.await
+				.ok(),
+		})
+		.await?;
+
+	// We do not add the event_id field to the pdu here because of signature and
+	// hashes checks
+	let (event_id, value) = gen_event_id_canonical_json(&response.event, &room_version_id)
+		.map_err(|e| {
+			err!(Request(BadJson(warn!("Could not convert event to canonical JSON: {e}"))))
+		})?;
+
+	if pdu.event_id != event_id {
+		return Err!(Request(BadJson(warn!(
+			%pdu.event_id, %event_id,
+			"Server {} sent event with wrong event ID", body.origin()
+		))));
+	}
+
+	let pdu_id = services
+		.rooms
+		.event_handler
+		.handle_incoming_pdu(body.origin(), &body.room_id, &event_id, value, true)
+		.boxed()
+		.await?
+		.ok_or_else(|| {
+			err!(Request(InvalidParam("Could not accept incoming PDU as timeline event.")))
+		})?;
+
+	services
+		.sending
+		.send_pdu_room(&body.room_id, &pdu_id)
+		.await?;
+
+	Ok(exchange_invite::v1::Response::new())
+}
@@ -51,8 +51,32 @@ pub(super) async fn check(&self) -> Result {
 		})
 		.into();
 
-	let (world_readable, server_in_room, server_can_see, acl_check, user_is_knocking) =
-		join!(world_readable, server_in_room, server_can_see, acl_check, user_is_knocking);
+	let event_in_room: OptionFuture<_> = self
+		.event_id
+		.map(|event_id| {
+			self.services
+				.rooms
+				.timeline
+				.get_pdu(event_id)
+				.map(|pdu| pdu.is_ok_and(|pdu| *pdu.room_id == *self.room_id))
+		})
+		.into();
+
+	let (
+		world_readable,
+		server_in_room,
+		server_can_see,
+		event_in_room,
+		acl_check,
+		user_is_knocking,
+	) = join!(
+		world_readable,
+		server_in_room,
+		server_can_see,
+		event_in_room,
+		acl_check,
+		user_is_knocking
+	);
 
 	if !acl_check {
 		return Err!(Request(Forbidden("Server access denied.")));
@@ -62,6 +86,10 @@ pub(super) async fn check(&self) -> Result {
 		return Err!(Request(Forbidden("Server is not in room.")));
 	}
 
+	if event_in_room.is_some_and(is_false!()) {
+		return Err!(Request(NotFound("Event not found in room.")));
+	}
+
 	if server_can_see.is_some_and(is_false!()) {
 		return Err!(Request(Forbidden("Server is not allowed to see event.")));
 	}
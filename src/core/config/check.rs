@@ -38,6 +38,17 @@ pub fn check(config: &Config) -> Result {
 		));
 	}
 
+	if config.smtp_url.is_some() && config.smtp_from.is_none() {
+		return Err!(Config("smtp_from", "A sender address is required to send emails"));
+	}
+
+	if config.require_email_for_registration && config.smtp_url.is_none() {
+		return Err!(Config(
+			"require_email_for_registration",
+			"Requiring an email address to register needs an SMTP server in 'smtp_url'"
+		));
+	}
+
 	if cfg!(all(feature = "hardened_malloc", feature = "jemalloc", not(target_env = "msvc"))) {
 		debug_warn!(
 			"hardened_malloc and jemalloc compile-time features are both enabled, this causes \
@@ -1,3 +1,4 @@
+use ruma::events::{room::member::RoomMemberEventContent, TimelineEventType};
 use serde::Deserialize;
 use serde_json::value::Value as JsonValue;
 
@@ -18,3 +19,18 @@ pub fn get_content<T>(&self) -> Result<T>
 	serde_json::from_str(self.content.get())
 		.map_err(|e| err!(Database("Failed to deserialize pdu content into type: {e}")))
 }
+
+/// Returns the token of the `m.room.third_party_invite` event this membership
+/// event converts, if any.
+#[must_use]
+#[implement(super::Pdu)]
+pub fn third_party_invite_token(&self) -> Option<String> {
+	if self.kind != TimelineEventType::RoomMember {
+		return None;
+	}
+
+	self.get_content::<RoomMemberEventContent>()
+		.ok()?
+		.third_party_invite
+		.map(|third_party_invite| third_party_invite.signed.token)
+}
@@ -1,9 +1,10 @@
 use ruma::{CanonicalJsonObject, OwnedEventId, RoomVersionId};
 use serde_json::value::RawValue as RawJsonValue;
 
-use crate::{err, Result};
+use crate::{err, utils::json::parse_canonical_object, Result};
 
-/// Generates a correct eventId for the incoming pdu.
+/// Generates a correct eventId for the incoming pdu, rejecting it if it is not
+/// valid canonical JSON.
 ///
 /// Returns a tuple of the new `EventId` and the PDU as a `BTreeMap<String,
 /// CanonicalJsonValue>`.
@@ -11,7 +12,7 @@ pub fn gen_event_id_canonical_json(
 	pdu: &RawJsonValue,
 	room_version_id: &RoomVersionId,
 ) -> Result<(OwnedEventId, CanonicalJsonObject)> {
-	let value: CanonicalJsonObject = serde_json::from_str(pdu.get())
+	let value = parse_canonical_object(pdu.get())
 		.map_err(|e| err!(BadServerResponse(warn!("Error parsing incoming event: {e:?}"))))?;
 
 	let event_id = gen_event_id(&value, room_version_id)?;
@@ -0,0 +1,46 @@
+use ruma::{CanonicalJsonObject, CanonicalJsonValue};
+
+use crate::{Err, Result};
+
+/// Maximum size of a PDU in its canonical JSON form.
+pub const MAX_PDU_BYTES: usize = 65_535;
+
+/// Maximum size of the identifier and type fields of a PDU.
+pub const MAX_PDU_FIELD_BYTES: usize = 255;
+
+/// Maximum number of events a PDU may reference in `prev_events`.
+pub const MAX_PREV_EVENTS: usize = 20;
+
+/// Maximum number of events a PDU may reference in `auth_events`.
+pub const MAX_AUTH_EVENTS: usize = 10;
+
+/// Checks the PDU against the size and field limits from the spec.
+pub fn check_pdu_limits(pdu: &CanonicalJsonObject) -> Result {
+	for field in ["event_id", "room_id", "sender", "state_key", "type"] {
+		if pdu
+			.get(field)
+			.and_then(CanonicalJsonValue::as_str)
+			.is_some_and(|value| value.len() > MAX_PDU_FIELD_BYTES)
+		{
+			return Err!(Request(InvalidParam(
+				"PDU {field} exceeds {MAX_PDU_FIELD_BYTES} bytes."
+			)));
+		}
+	}
+
+	for (field, limit) in [("prev_events", MAX_PREV_EVENTS), ("auth_events", MAX_AUTH_EVENTS)] {
+		if pdu
+			.get(field)
+			.and_then(CanonicalJsonValue::as_array)
+			.is_some_and(|events| events.len() > limit)
+		{
+			return Err!(Request(InvalidParam("PDU references more than {limit} {field}.")));
+		}
+	}
+
+	if serde_json::to_vec(pdu)?.len() > MAX_PDU_BYTES {
+		return Err!(Request(TooLarge("PDU exceeds {MAX_PDU_BYTES} bytes.")));
+	}
+
+	Ok(())
+}
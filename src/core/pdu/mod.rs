@@ -5,6 +5,7 @@
 mod event_id;
 mod filter;
 mod id;
+mod limits;
 mod raw_id;
 mod redact;
 mod relation;
@@ -28,6 +29,7 @@
 	event::Event,
 	event_id::*,
 	id::*,
+	limits::*,
 	raw_id::*,
 	Count as PduCount, Id as PduId, Pdu as PduEvent, RawId as RawPduId,
 };
@@ -1,4 +1,6 @@
-use super::Count;
+use ruma::{CanonicalJsonObject, CanonicalJsonValue};
+
+use super::{check_pdu_limits, Count, MAX_PDU_FIELD_BYTES, MAX_PREV_EVENTS};
 
 #[test]
 fn backfilled_parse() {
@@ -15,3 +17,24 @@ fn normal_parse() {
 
 	assert!(!backfilled, "backfilled variant");
 }
+
+#[test]
+fn pdu_limits_field_length() {
+	let mut pdu = CanonicalJsonObject::new();
+	pdu.insert("type".into(), CanonicalJsonValue::String("m.room.message".into()));
+	assert!(check_pdu_limits(&pdu).is_ok(), "valid pdu rejected");
+
+	let kind = "a".repeat(MAX_PDU_FIELD_BYTES.saturating_add(1));
+	pdu.insert("type".into(), CanonicalJsonValue::String(kind));
+	assert!(check_pdu_limits(&pdu).is_err(), "oversized type accepted");
+}
+
+#[test]
+fn pdu_limits_prev_events() {
+	let prev_event = CanonicalJsonValue::String("$event".into());
+	let prev_events = vec![prev_event; MAX_PREV_EVENTS.saturating_add(1)];
+
+	let mut pdu = CanonicalJsonObject::new();
+	pdu.insert("prev_events".into(), CanonicalJsonValue::Array(prev_events));
+	assert!(check_pdu_limits(&pdu).is_err(), "too many prev_events accepted");
+}
@@ -1,7 +1,7 @@
 use std::collections::BTreeMap;
 
 use ruma::MilliSecondsSinceUnixEpoch;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::value::{to_raw_value, RawValue as RawJsonValue, Value as JsonValue};
 
 use super::Pdu;
@@ -46,7 +46,10 @@ pub fn add_age(&mut self) -> Result {
 }
 
 #[implement(Pdu)]
-pub fn add_relation(&mut self, name: &str, pdu: &Pdu) -> Result {
+pub fn add_relation<T>(&mut self, name: &str, relation: &T) -> Result
+where
+	T: Serialize + ?Sized,
+{
 	let mut unsigned: BTreeMap<String, JsonValue> = self
 		.unsigned
 		.as_ref()
@@ -62,7 +65,7 @@ pub fn add_relation(&mut self, name: &str, pdu: &Pdu) -> Result {
 	relations
 		.as_object_mut()
 		.expect("we just created it")
-		.insert(name.to_owned(), serde_json::to_value(pdu)?);
+		.insert(name.to_owned(), serde_json::to_value(relation)?);
 
 	self.unsigned = to_raw_value(&unsigned)
 		.map(Some)
@@ -1,6 +1,13 @@
-use std::{fmt, str::FromStr};
+use std::{collections::btree_map, fmt, str::FromStr};
 
-use ruma::{canonical_json::try_from_json_map, CanonicalJsonError, CanonicalJsonObject};
+use ruma::{
+	canonical_json::try_from_json_map, CanonicalJsonError, CanonicalJsonObject,
+	CanonicalJsonValue, Int,
+};
+use serde::{
+	de::{self, MapAccess, SeqAccess, Visitor},
+	Deserialize, Deserializer,
+};
 
 use crate::Result;
 
@@ -45,3 +52,95 @@ fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
 	}
 	deserializer.deserialize_str(Visitor(std::marker::PhantomData))
 }
+
+/// Parses a `CanonicalJsonObject`, failing if any object contains the same
+/// key more than once. Such JSON has no canonical form, yet deserializing a
+/// `CanonicalJsonObject` directly silently keeps the last value.
+pub fn parse_canonical_object(json: &str) -> Result<CanonicalJsonObject, serde_json::Error> {
+	serde_json::from_str::<UniqueKeys<CanonicalJsonObject>>(json).map(|UniqueKeys(object)| object)
+}
+
+struct UniqueKeys<T>(T);
+
+impl<'de> Deserialize<'de> for UniqueKeys<CanonicalJsonObject> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		match deserializer.deserialize_map(UniqueKeysVisitor)? {
+			| CanonicalJsonValue::Object(object) => Ok(Self(object)),
+			| _ => Err(de::Error::custom("expected a JSON object")),
+		}
+	}
+}
+
+impl<'de> Deserialize<'de> for UniqueKeys<CanonicalJsonValue> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		deserializer.deserialize_any(UniqueKeysVisitor).map(Self)
+	}
+}
+
+struct UniqueKeysVisitor;
+
+impl<'de> Visitor<'de> for UniqueKeysVisitor {
+	type Value = CanonicalJsonValue;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(formatter, "a canonical JSON value")
+	}
+
+	fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+		Ok(CanonicalJsonValue::Bool(v))
+	}
+
+	fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+		Int::new(v)
+			.map(CanonicalJsonValue::Integer)
+			.ok_or_else(|| E::custom(format!("integer out of range: {v}")))
+	}
+
+	fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+		Int::try_from(v)
+			.map(CanonicalJsonValue::Integer)
+			.map_err(|_| E::custom(format!("integer out of range: {v}")))
+	}
+
+	fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+		Err(E::custom(format!("floats are not allowed: {v}")))
+	}
+
+	fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+		Ok(CanonicalJsonValue::String(v.to_owned()))
+	}
+
+	fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+		Ok(CanonicalJsonValue::String(v))
+	}
+
+	fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> { Ok(CanonicalJsonValue::Null) }
+
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+		let mut array = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+		while let Some(UniqueKeys(value)) =
+			seq.next_element::<UniqueKeys<CanonicalJsonValue>>()?
+		{
+			array.push(value);
+		}
+
+		Ok(CanonicalJsonValue::Array(array))
+	}
+
+	fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+		let mut object = CanonicalJsonObject::new();
+		while let Some(key) = map.next_key::<String>()? {
+			let UniqueKeys(value) = map.next_value::<UniqueKeys<CanonicalJsonValue>>()?;
+			match object.entry(key) {
+				| btree_map::Entry::Vacant(entry) => {
+					entry.insert(value);
+				},
+				| btree_map::Entry::Occupied(entry) => {
+					return Err(de::Error::custom(format!("duplicate key {:?}", entry.key())));
+				},
+			}
+		}
+
+		Ok(CanonicalJsonValue::Object(object))
+	}
+}
@@ -276,3 +276,15 @@ async fn set_intersection_sorted_stream2() {
 		.await;
 	assert!(r.eq(&["ccc", "ggg", "iii"]));
 }
+
+#[test]
+fn json_duplicate_keys() {
+	use utils::json::parse_canonical_object;
+
+	let object = parse_canonical_object(r#"{"a":1,"b":{"a":[1,{"c":null}]}}"#).unwrap();
+	assert_eq!(serde_json::to_string(&object).unwrap(), r#"{"a":1,"b":{"a":[1,{"c":null}]}}"#);
+	assert!(parse_canonical_object(r#"{"a":1,"a":2}"#).is_err());
+	assert!(parse_canonical_object(r#"{"a":[{"b":true,"b":false}]}"#).is_err());
+	assert!(parse_canonical_object(r#"{"a":1.5}"#).is_err());
+	assert!(parse_canonical_object("[]").is_err());
+}
@@ -0,0 +1,357 @@
+use std::{
+	collections::HashMap,
+	hash::Hash,
+	net::IpAddr,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use conduwuit::{debug_info, err, error, implement, utils, Err, Error, Result};
+use database::{Deserialized, Map};
+use lettre::{
+	message::{header::ContentType, Mailbox},
+	Address, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use ruma::{
+	api::client::error::{ErrorKind, RetryAfter},
+	OwnedUserId, UInt, UserId,
+};
+use url::Url;
+
+use crate::{config, globals, Dep};
+
+pub struct Service {
+	sessions: Mutex<HashMap<String, Session>>,
+	sent: Mutex<Sent>,
+	smtp: Option<Smtp>,
+	db: Data,
+	services: Services,
+}
+
+struct Smtp {
+	transport: AsyncSmtpTransport<Tokio1Executor>,
+	from: Mailbox,
+}
+
+struct Data {
+	email_userid: Arc<Map>,
+	userid_email: Arc<Map>,
+}
+
+struct Services {
+	config: Dep<config::Service>,
+	globals: Dep<globals::Service>,
+}
+
+/// A validation session for an email address, identified by its session ID.
+struct Session {
+	email: String,
+	client_secret: String,
+	token: String,
+	send_attempt: UInt,
+	next_link: Option<Url>,
+	validated: bool,
+	created: Instant,
+}
+
+/// When validation emails were sent recently, by the address they were sent to
+/// and by the IP address they were requested from.
+#[derive(Default)]
+struct Sent {
+	by_address: HashMap<String, Vec<Instant>>,
+	by_client: HashMap<IpAddr, Vec<Instant>>,
+}
+
+/// Path of the validation links sent in emails.
+pub const VALIDATION_PATH: &str = "/_conduwuit/email/validate";
+
+/// How long users have to follow the validation link, and to use the
+/// validated session afterwards.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// How many validation emails can be sent to one address, and requested from
+/// one IP address, within the `RATELIMIT_WINDOW`.
+const EMAILS_PER_ADDRESS: usize = 3;
+const EMAILS_PER_CLIENT: usize = 10;
+const RATELIMIT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// How many validation sessions can be pending at once.
+const MAX_SESSIONS: usize = 10_000;
+
+const SESSION_ID_LENGTH: usize = 32;
+const TOKEN_LENGTH: usize = 32;
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		let config = &args.server.config;
+		let smtp = match (&config.smtp_url, &config.smtp_from) {
+			| (Some(url), Some(from)) => Some(Smtp {
+				transport: AsyncSmtpTransport::<Tokio1Executor>::from_url(url.as_str())
+					.map_err(|e| err!(Config("smtp_url", "Invalid SMTP URL: {e}")))?
+					.build(),
+				from: from
+					.parse()
+					.map_err(|e| err!(Config("smtp_from", "Invalid sender address: {e}")))?,
+			}),
+			| _ => None,
+		};
+
+		Ok(Arc::new(Self {
+			sessions: Mutex::new(HashMap::new()),
+			sent: Mutex::default(),
+			smtp,
+			db: Data {
+				email_userid: args.db["email_userid"].clone(),
+				userid_email: args.db["userid_email"].clone(),
+			},
+			services: Services {
+				config: args.depend::<config::Service>("config"),
+				globals: args.depend::<globals::Service>("globals"),
+			},
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Whether an SMTP server is configured to send validation emails with.
+#[implement(Service)]
+#[must_use]
+pub fn is_enabled(&self) -> bool { self.smtp.is_some() }
+
+/// Whether new users must validate an email address to register.
+#[implement(Service)]
+#[must_use]
+pub fn required_for_registration(&self) -> bool {
+	self.is_enabled() && self.services.config.require_email_for_registration
+}
+
+/// Starts validating an email address by sending it a link with a token,
+/// returning the session ID. Requests repeating an earlier `send_attempt` for
+/// the same address and client secret return the existing session without
+/// sending another email. The emails sent to each address and requested by
+/// each client are rate limited.
+#[implement(Service)]
+pub async fn request_token(
+	&self,
+	email: &str,
+	client_secret: &str,
+	send_attempt: UInt,
+	next_link: Option<&str>,
+	client: IpAddr,
+) -> Result<String> {
+	let Some(smtp) = &self.smtp else {
+		return Err!(Request(ThreepidDenied("Email verification is disabled.")));
+	};
+
+	let email = normalize(email)?;
+	let next_link = next_link
+		.map(Url::parse)
+		.transpose()
+		.map_err(|e| err!(Request(InvalidParam("Invalid next_link: {e}"))))?;
+
+	if next_link
+		.as_ref()
+		.is_some_and(|next_link| !matches!(next_link.scheme(), "http" | "https"))
+	{
+		return Err!(Request(InvalidParam("next_link must be an http or https URL.")));
+	}
+
+	let (sid, token) = {
+		let mut sessions = self.sessions.lock().expect("locked");
+		sessions.retain(|_, session| session.created.elapsed() < SESSION_TIMEOUT);
+
+		let existing = sessions.iter_mut().find(|(_, session)| {
+			session.email == email && session.client_secret == client_secret
+		});
+
+		match existing {
+			| Some((sid, session)) if session.send_attempt >= send_attempt => {
+				return Ok(sid.clone());
+			},
+			| Some((sid, session)) => {
+				self.ratelimit(&email, client)?;
+				session.send_attempt = send_attempt;
+				session.next_link = next_link;
+				(sid.clone(), session.token.clone())
+			},
+			| None => {
+				if sessions.len() >= MAX_SESSIONS {
+					return Err(Error::BadRequest(
+						ErrorKind::LimitExceeded { retry_after: None },
+						"Too many pending email validations.",
+					));
+				}
+
+				self.ratelimit(&email, client)?;
+				let sid = utils::random_string(SESSION_ID_LENGTH);
+				let token = utils::random_string(TOKEN_LENGTH);
+				sessions.insert(sid.clone(), Session {
+					email: email.clone(),
+					client_secret: client_secret.to_owned(),
+					token: token.clone(),
+					send_attempt,
+					next_link,
+					validated: false,
+					created: Instant::now(),
+				});
+
+				(sid, token)
+			},
+		}
+	};
+
+	let mut link = self
+		.services
+		.globals
+		.client_url()?
+		.join(VALIDATION_PATH)
+		.map_err(|e| err!("Failed to build the validation link: {e}"))?;
+
+	link.query_pairs_mut()
+		.append_pair("sid", &sid)
+		.append_pair("client_secret", client_secret)
+		.append_pair("token", &token);
+
+	let server_name = self.services.globals.server_name();
+	let message = Message::builder()
+		.from(smtp.from.clone())
+		.to(Mailbox::new(None, email.parse().expect("address was validated")))
+		.subject(format!("Validate your email address on {server_name}"))
+		.header(ContentType::TEXT_PLAIN)
+		.body(format!(
+			"Someone requested to use this email address with a Matrix account on \
+			 {server_name}. If this was you, follow this link to validate it:\n\n{link}\n\nIf \
+			 this wasn't you, you can ignore this email."
+		))
+		.map_err(|e| err!("Failed to build validation email: {e}"))?;
+
+	smtp.transport
+		.send(message)
+		.await
+		.map_err(|e| err!(BadServerResponse(error!("Failed to send validation email: {e}"))))?;
+
+	debug_info!(%sid, "Sent email validation link");
+
+	Ok(sid)
+}
+
+/// Validates a session with the token from the link sent to its address,
+/// returning the link the user asked to be sent to afterwards.
+#[implement(Service)]
+pub fn submit_token(&self, sid: &str, client_secret: &str, token: &str) -> Result<Option<Url>> {
+	let mut sessions = self.sessions.lock().expect("locked");
+	let session = sessions
+		.get_mut(sid)
+		.filter(|session| session.created.elapsed() < SESSION_TIMEOUT)
+		.filter(|session| session.client_secret == client_secret && session.token == token)
+		.ok_or_else(|| err!(Request(Forbidden("Unknown or expired validation token."))))?;
+
+	session.validated = true;
+
+	Ok(session.next_link.clone())
+}
+
+/// The email address of a session once its link was followed.
+#[implement(Service)]
+pub fn validated_email(&self, sid: &str, client_secret: &str) -> Option<String> {
+	self.sessions
+		.lock()
+		.expect("locked")
+		.get(sid)
+		.filter(|session| session.created.elapsed() < SESSION_TIMEOUT)
+		.filter(|session| session.validated && session.client_secret == client_secret)
+		.map(|session| session.email.clone())
+}
+
+/// The local user an email address is associated with.
+#[implement(Service)]
+pub async fn user_for_email(&self, email: &str) -> Result<OwnedUserId> {
+	self.db
+		.email_userid
+		.get(&normalize(email)?)
+		.await
+		.deserialized()
+}
+
+/// The email address associated with a user.
+#[implement(Service)]
+pub async fn email_for_user(&self, user_id: &UserId) -> Result<String> {
+	self.db
+		.userid_email
+		.get(user_id)
+		.await
+		.deserialized()
+}
+
+/// Associates a validated email address with a user, replacing any address
+/// the user had before.
+#[implement(Service)]
+pub async fn set_email(&self, user_id: &UserId, email: &str) -> Result {
+	let email = normalize(email)?;
+	self.remove_email(user_id).await;
+	self.db.email_userid.insert(&email, user_id);
+	self.db.userid_email.insert(user_id, &email);
+
+	Ok(())
+}
+
+/// Removes the email address associated with a user, if any.
+#[implement(Service)]
+pub async fn remove_email(&self, user_id: &UserId) {
+	if let Ok(email) = self.email_for_user(user_id).await {
+		self.db.email_userid.remove(&email);
+		self.db.userid_email.remove(user_id);
+	}
+}
+
+/// Records an email about to be sent to the address on behalf of the client,
+/// unless either of them reached their limit within the `RATELIMIT_WINDOW`.
+#[implement(Service)]
+fn ratelimit(&self, email: &str, client: IpAddr) -> Result {
+	let mut sent = self.sent.lock().expect("locked");
+	let Sent { by_address, by_client } = &mut *sent;
+	expire(by_address);
+	expire(by_client);
+
+	let address = by_address.entry(email.to_owned()).or_default();
+	let client = by_client.entry(client).or_default();
+	let wait = |sent: &[Instant], limit: usize| {
+		let oldest = sent.len().checked_sub(limit).and_then(|i| sent.get(i))?;
+		Some(RATELIMIT_WINDOW.saturating_sub(oldest.elapsed()))
+	};
+
+	let retry_after = wait(address, EMAILS_PER_ADDRESS).max(wait(client, EMAILS_PER_CLIENT));
+	if let Some(retry_after) = retry_after {
+		return Err(Error::BadRequest(
+			ErrorKind::LimitExceeded {
+				retry_after: Some(RetryAfter::Delay(retry_after)),
+			},
+			"Too many validation emails requested.",
+		));
+	}
+
+	let now = Instant::now();
+	address.push(now);
+	client.push(now);
+
+	Ok(())
+}
+
+/// Forgets the emails sent before the `RATELIMIT_WINDOW`.
+fn expire<K: Eq + Hash>(sent: &mut HashMap<K, Vec<Instant>>) {
+	sent.retain(|_, sent| {
+		sent.retain(|sent| sent.elapsed() < RATELIMIT_WINDOW);
+		!sent.is_empty()
+	});
+}
+
+/// Email addresses are compared case-insensitively.
+fn normalize(email: &str) -> Result<String> {
+	let email = email.trim().to_lowercase();
+	email
+		.parse::<Address>()
+		.map_err(|e| err!(Request(InvalidParam("Invalid email address: {e}"))))?;
+
+	Ok(email)
+}
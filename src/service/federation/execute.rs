@@ -1,9 +1,14 @@
-use std::{fmt::Debug, mem};
+use std::{collections::hash_map, fmt::Debug, mem, time::Instant};
 
 use bytes::Bytes;
 use conduwuit::{
-	debug, debug::INFO_SPAN_LEVEL, debug_error, debug_warn, err, error::inspect_debug_log,
-	implement, trace, utils::string::EMPTY, Err, Error, Result,
+	debug,
+	debug::INFO_SPAN_LEVEL,
+	debug_error, debug_warn, err,
+	error::inspect_debug_log,
+	implement, trace,
+	utils::{continue_exponential_backoff_secs, string::EMPTY},
+	Err, Error, Result,
 };
 use http::{header::AUTHORIZATION, HeaderValue};
 use ipaddress::IPAddress;
@@ -75,6 +80,19 @@ pub async fn execute_on<T>(
 		return Err!(Request(Forbidden(debug_warn!("Federation with {dest} is not allowed."))));
 	}
 
+	let allowed = &self.services.server.config.allowed_remote_server_names;
+	if !allowed.is_empty() && !allowed.contains(dest) {
+		return Err!(Request(Forbidden(debug_warn!(
+			"Federation with {dest} is not in the allowed list."
+		))));
+	}
+
+	if let Some(tries) = self.backed_off(dest) {
+		return Err!(BadServerResponse(debug_warn!(
+			"Backing off from {dest} after {tries} failed requests."
+		)));
+	}
+
 	let actual = self.services.resolver.get_actual_dest(dest).await?;
 	let request = into_http_request::<T>(&actual, request)?;
 	let request = self.prepare(dest, request)?;
@@ -96,11 +114,76 @@ async fn perform<T>(
 	let method = request.method().clone();
 
 	debug!(?method, ?url, "Sending request");
-	match client.execute(request).await {
-		| Ok(response) => handle_response::<T>(dest, actual, &method, &url, response).await,
-		| Err(error) =>
-			Err(handle_error(actual, &method, &url, error).expect_err("always returns error")),
+	let timer = Instant::now();
+	let result = match client.execute(request).await {
+		| Ok(response) => {
+			self.back_off_reset(dest);
+			handle_response::<T>(dest, actual, &method, &url, response).await
+		},
+		| Err(error) => {
+			self.back_off(dest);
+			Err(handle_error(actual, &method, &url, error).expect_err("always returns error"))
+		},
+	};
+
+	self.metrics.record(dest, timer.elapsed(), result.is_ok());
+	result
+}
+
+/// Returns the number of consecutive failed requests to the destination if we
+/// are still backing off from it. Backing off starts once
+/// `federation_backoff_threshold` requests in a row failed.
+#[implement(super::Service)]
+fn backed_off(&self, dest: &ServerName) -> Option<u32> {
+	let config = &self.services.server.config;
+	let threshold = config.federation_backoff_threshold;
+	if threshold == 0 {
+		return None;
 	}
+
+	let min = config.sender_timeout;
+	let max = config.sender_retry_backoff_limit;
+	self.services
+		.globals
+		.bad_destination_ratelimiter
+		.read()
+		.expect("locked")
+		.get(dest)
+		.filter(|(_, tries)| *tries >= threshold)
+		.filter(|(time, tries)| {
+			let tries = tries.saturating_sub(threshold).saturating_add(1);
+			continue_exponential_backoff_secs(min, max, time.elapsed(), tries)
+		})
+		.map(|(_, tries)| *tries)
+}
+
+#[implement(super::Service)]
+fn back_off(&self, dest: &ServerName) {
+	match self
+		.services
+		.globals
+		.bad_destination_ratelimiter
+		.write()
+		.expect("locked")
+		.entry(dest.to_owned())
+	{
+		| hash_map::Entry::Vacant(e) => {
+			e.insert((Instant::now(), 1));
+		},
+		| hash_map::Entry::Occupied(mut e) => {
+			*e.get_mut() = (Instant::now(), e.get().1.saturating_add(1));
+		},
+	}
+}
+
+#[implement(super::Service)]
+fn back_off_reset(&self, dest: &ServerName) {
+	self.services
+		.globals
+		.bad_destination_ratelimiter
+		.write()
+		.expect("locked")
+		.remove(dest);
 }
 
 #[implement(super::Service)]
@@ -0,0 +1,95 @@
+use std::{collections::HashMap, sync::RwLock, time::Duration};
+
+use ruma::{OwnedServerName, ServerName};
+
+/// Upper bounds of the request latency histogram buckets.
+pub const LATENCY_BUCKETS: [Duration; 6] = [
+	Duration::from_millis(100),
+	Duration::from_millis(250),
+	Duration::from_secs(1),
+	Duration::from_secs(5),
+	Duration::from_secs(30),
+	Duration::MAX,
+];
+
+/// Outgoing federation request statistics for each destination.
+#[derive(Default)]
+pub struct Metrics {
+	destinations: RwLock<HashMap<OwnedServerName, Destination>>,
+}
+
+/// Outgoing federation request statistics for a single destination.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Destination {
+	pub requests: u64,
+	pub errors: u64,
+	pub latency_total: Duration,
+	pub latency_max: Duration,
+	pub latency_buckets: [u64; LATENCY_BUCKETS.len()],
+}
+
+impl Metrics {
+	pub(super) fn record(&self, dest: &ServerName, latency: Duration, success: bool) {
+		let mut destinations = self.destinations.write().expect("locked for writing");
+		let stats = destinations.entry(dest.to_owned()).or_default();
+
+		stats.requests = stats.requests.saturating_add(1);
+		stats.errors = stats.errors.saturating_add((!success).into());
+		stats.latency_total = stats.latency_total.saturating_add(latency);
+		stats.latency_max = stats.latency_max.max(latency);
+
+		let bucket = LATENCY_BUCKETS
+			.iter()
+			.position(|bound| latency <= *bound)
+			.unwrap_or(LATENCY_BUCKETS.len().saturating_sub(1));
+
+		if let Some(count) = stats.latency_buckets.get_mut(bucket) {
+			*count = count.saturating_add(1);
+		}
+	}
+
+	/// Statistics for every destination we have sent requests to, sorted by
+	/// server name.
+	#[must_use]
+	pub fn destinations(&self) -> Vec<(OwnedServerName, Destination)> {
+		let mut destinations: Vec<_> = self
+			.destinations
+			.read()
+			.expect("locked for reading")
+			.iter()
+			.map(|(dest, stats)| (dest.clone(), *stats))
+			.collect();
+
+		destinations.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+		destinations
+	}
+
+	#[must_use]
+	pub fn destination(&self, dest: &ServerName) -> Option<Destination> {
+		self.destinations
+			.read()
+			.expect("locked for reading")
+			.get(dest)
+			.copied()
+	}
+
+	pub(super) fn len(&self) -> usize {
+		self.destinations.read().expect("locked for reading").len()
+	}
+
+	pub(super) fn clear(&self) {
+		self.destinations
+			.write()
+			.expect("locked for writing")
+			.clear();
+	}
+}
+
+impl Destination {
+	/// Mean latency over all requests to the destination.
+	#[must_use]
+	pub fn latency_mean(&self) -> Duration {
+		let requests = u32::try_from(self.requests).unwrap_or(u32::MAX);
+		self.latency_total.checked_div(requests).unwrap_or_default()
+	}
+}
@@ -1,18 +1,22 @@
 mod execute;
+pub mod metrics;
 
-use std::sync::Arc;
+use std::{fmt::Write, sync::Arc};
 
 use conduwuit::{Result, Server};
 
-use crate::{client, resolver, server_keys, Dep};
+use self::metrics::Metrics;
+use crate::{client, globals, resolver, server_keys, Dep};
 
 pub struct Service {
 	services: Services,
+	pub metrics: Metrics,
 }
 
 struct Services {
 	server: Arc<Server>,
 	client: Dep<client::Service>,
+	globals: Dep<globals::Service>,
 	resolver: Dep<resolver::Service>,
 	server_keys: Dep<server_keys::Service>,
 }
@@ -23,11 +27,22 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 			services: Services {
 				server: args.server.clone(),
 				client: args.depend::<client::Service>("client"),
+				globals: args.depend::<globals::Service>("globals"),
 				resolver: args.depend::<resolver::Service>("resolver"),
 				server_keys: args.depend::<server_keys::Service>("server_keys"),
 			},
+			metrics: Metrics::default(),
 		}))
 	}
 
+	fn memory_usage(&self, out: &mut dyn Write) -> Result {
+		let destinations = self.metrics.len();
+		writeln!(out, "destination_metrics: {destinations}")?;
+
+		Ok(())
+	}
+
+	fn clear_cache(&self) { self.metrics.clear(); }
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
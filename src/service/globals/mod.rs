@@ -4,13 +4,14 @@
 	collections::HashMap,
 	fmt::Write,
 	sync::{Arc, RwLock},
-	time::Instant,
+	time::{Duration, Instant},
 };
 
-use conduwuit::{error, utils::bytes::pretty, Result, Server};
+use conduwuit::{err, error, utils::bytes::pretty, Result, Server};
 use data::Data;
 use regex::RegexSet;
 use ruma::{OwnedEventId, OwnedRoomAliasId, OwnedServerName, OwnedUserId, ServerName, UserId};
+use url::Url;
 
 use crate::service;
 
@@ -19,6 +20,8 @@ pub struct Service {
 	server: Arc<Server>,
 
 	pub bad_event_ratelimiter: Arc<RwLock<HashMap<OwnedEventId, RateLimitState>>>,
+	pub bad_destination_ratelimiter: Arc<RwLock<HashMap<OwnedServerName, RateLimitState>>>,
+	federation_ratelimiter: RwLock<HashMap<OwnedServerName, TokenBucket>>,
 	pub server_user: OwnedUserId,
 	pub admin_alias: OwnedRoomAliasId,
 	pub turn_secret: String,
@@ -26,6 +29,7 @@ pub struct Service {
 }
 
 type RateLimitState = (Instant, u32); // Time if last failed try, number of failed tries
+type TokenBucket = (Instant, f64); // Time of last refill, number of tokens left
 
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
@@ -61,6 +65,8 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 			db,
 			server: args.server.clone(),
 			bad_event_ratelimiter: Arc::new(RwLock::new(HashMap::new())),
+			bad_destination_ratelimiter: Arc::new(RwLock::new(HashMap::new())),
+			federation_ratelimiter: RwLock::new(HashMap::new()),
 			admin_alias: OwnedRoomAliasId::try_from(format!("#admins:{}", &args.server.name))
 				.expect("#admins:server_name is valid alias name"),
 			server_user: UserId::parse_with_server_name(
@@ -94,6 +100,16 @@ fn clear_cache(&self) {
 			.write()
 			.expect("locked for writing")
 			.clear();
+
+		self.bad_destination_ratelimiter
+			.write()
+			.expect("locked for writing")
+			.clear();
+
+		self.federation_ratelimiter
+			.write()
+			.expect("locked for writing")
+			.clear();
 	}
 
 	fn name(&self) -> &str { service::make_name(std::module_path!()) }
@@ -109,6 +125,46 @@ pub fn current_count(&self) -> Result<u64> { Ok(self.db.current_count()) }
 	#[inline]
 	pub fn server_name(&self) -> &ServerName { self.server.name.as_ref() }
 
+	/// Takes a token from the origin's federation request bucket. Returns how
+	/// long the origin has to wait before retrying if the bucket is empty.
+	pub fn federation_ratelimit(&self, origin: &ServerName) -> Option<Duration> {
+		let config = &self.server.config;
+		if config.federation_ratelimit_per_second == 0 {
+			return None;
+		}
+
+		let rate = f64::from(config.federation_ratelimit_per_second);
+		let burst = f64::from(config.federation_ratelimit_burst.max(1));
+		let now = Instant::now();
+
+		let mut buckets = self
+			.federation_ratelimiter
+			.write()
+			.expect("locked for writing");
+
+		// Before the map has to grow, forget the buckets which have refilled since;
+		// they're no different from a new bucket. This keeps the map to the origins
+		// recently sending requests, at an amortized constant cost.
+		if buckets.len() >= buckets.capacity() && !buckets.contains_key(origin) {
+			buckets.retain(|_, (refilled, tokens)| {
+				let elapsed = now.saturating_duration_since(*refilled).as_secs_f64();
+				elapsed.mul_add(rate, *tokens) < burst
+			});
+		}
+
+		let (refilled, tokens) = buckets.entry(origin.to_owned()).or_insert((now, burst));
+		let elapsed = now.saturating_duration_since(*refilled).as_secs_f64();
+		*tokens = elapsed.mul_add(rate, *tokens).min(burst);
+		*refilled = now;
+
+		if *tokens >= 1.0 {
+			*tokens -= 1.0;
+			return None;
+		}
+
+		Some(Duration::from_secs_f64((1.0 - *tokens) / rate))
+	}
+
 	pub fn allow_registration(&self) -> bool { self.server.config.allow_registration }
 
 	pub fn allow_guest_registration(&self) -> bool { self.server.config.allow_guest_registration }
@@ -199,6 +255,15 @@ pub fn allow_outgoing_read_receipts(&self) -> bool {
 
 	pub fn block_non_admin_invites(&self) -> bool { self.server.config.block_non_admin_invites }
 
+	/// Base URL clients reach this server at, for links handed out to users.
+	pub fn client_url(&self) -> Result<Url> {
+		match &self.server.config.well_known.client {
+			| Some(client) => Ok(client.clone()),
+			| None => Url::parse(&format!("https://{}", self.server_name()))
+				.map_err(|e| err!("Server name is not a valid host: {e}")),
+		}
+	}
+
 	/// checks if `user_id` is local to us via server_name comparison
 	#[inline]
 	pub fn user_is_local(&self, user_id: &UserId) -> bool {
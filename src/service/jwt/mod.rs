@@ -0,0 +1,246 @@
+mod tests;
+
+use std::{
+	collections::HashMap,
+	sync::Arc,
+	time::{Duration, Instant, SystemTime},
+};
+
+use base64::{engine::general_purpose, Engine as _};
+use conduwuit::{debug_warn, err, implement, Err, Result};
+use ring::{hmac, signature};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tokio::sync::RwLock;
+use url::Url;
+
+use crate::{client, config, Dep};
+
+pub struct Service {
+	jwks: RwLock<HashMap<Url, (Instant, Jwks)>>,
+	services: Services,
+}
+
+struct Services {
+	client: Dep<client::Service>,
+	config: Dep<config::Service>,
+}
+
+/// Claims of a verified JSON web token.
+#[derive(Debug, Deserialize)]
+pub struct Claims {
+	pub sub: String,
+	pub exp: Option<f64>,
+	pub nbf: Option<f64>,
+
+	/// Every other claim in the token.
+	#[serde(flatten)]
+	pub other: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct Header {
+	alg: String,
+	kid: Option<String>,
+}
+
+struct Token<'a> {
+	header: Header,
+	claims: Claims,
+	signed: &'a str,
+	signature: Vec<u8>,
+}
+
+#[derive(Clone, Deserialize)]
+struct Jwks {
+	keys: Vec<Jwk>,
+}
+
+#[derive(Clone, Deserialize)]
+struct Jwk {
+	kty: String,
+	kid: Option<String>,
+	n: Option<String>,
+	e: Option<String>,
+	crv: Option<String>,
+	x: Option<String>,
+	y: Option<String>,
+}
+
+/// How long a fetched key set is used before it is fetched again.
+const JWKS_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Key sets are not refetched for unknown key IDs more often than this.
+const JWKS_REFETCH_MIN: Duration = Duration::from_secs(60);
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			jwks: RwLock::new(HashMap::new()),
+			services: Services {
+				client: args.depend::<client::Service>("client"),
+				config: args.depend::<config::Service>("config"),
+			},
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Verifies an `org.matrix.login.jwt` token against the configured shared
+/// secret or key set.
+#[implement(Service)]
+pub async fn verify_login_token(&self, token: &str) -> Result<Claims> {
+	let config = &self.services.config;
+	self.verify(token, config.jwt_secret.as_deref(), config.jwt_jwks_url.as_ref())
+		.await
+}
+
+/// Verifies a token's signature and validity period. HS256 tokens are checked
+/// against `secret`, RS256 and ES256 tokens against the key set at `jwks_url`.
+#[implement(Service)]
+pub async fn verify(
+	&self,
+	token: &str,
+	secret: Option<&str>,
+	jwks_url: Option<&Url>,
+) -> Result<Claims> {
+	let token = parse(token)?;
+	match (token.header.alg.as_str(), secret, jwks_url) {
+		| ("HS256", Some(secret), _) => verify_hmac(&token, secret.as_bytes())?,
+		| ("RS256" | "ES256", _, Some(jwks_url)) => {
+			let jwk = self
+				.find_jwk(jwks_url, token.header.kid.as_deref(), &token.header.alg)
+				.await?;
+			verify_jwk(&token, &jwk)?;
+		},
+		| _ => return Err!(Request(Forbidden("Unsupported JWT signing algorithm."))),
+	}
+
+	let now = SystemTime::UNIX_EPOCH
+		.elapsed()
+		.map_err(|e| err!("System time is before the unix epoch: {e}"))?
+		.as_secs_f64();
+
+	check_validity(&token.claims, now)?;
+
+	Ok(token.claims)
+}
+
+#[implement(Service)]
+async fn find_jwk(&self, url: &Url, kid: Option<&str>, alg: &str) -> Result<Jwk> {
+	let find = |jwks: &Jwks| {
+		jwks.keys
+			.iter()
+			.filter(|jwk| kid.is_none_or(|kid| jwk.kid.as_deref() == Some(kid)))
+			.find(|jwk| jwk.kty == key_type(alg))
+			.cloned()
+	};
+
+	let cached = self.jwks.read().await.get(url).cloned();
+	if let Some((fetched, jwks)) = &cached {
+		if fetched.elapsed() < JWKS_CACHE_TTL {
+			if let Some(jwk) = find(jwks) {
+				return Ok(jwk);
+			}
+
+			// The key set may have been rotated, but don't let unknown key IDs
+			// make us hammer the provider
+			if fetched.elapsed() < JWKS_REFETCH_MIN {
+				return Err!(Request(Forbidden("JWT signing key is unknown.")));
+			}
+		}
+	}
+
+	let jwks = self.fetch_jwks(url).await?;
+	let jwk = find(&jwks);
+	self.jwks
+		.write()
+		.await
+		.insert(url.clone(), (Instant::now(), jwks));
+
+	jwk.ok_or_else(|| err!(Request(Forbidden("JWT signing key is unknown."))))
+}
+
+#[implement(Service)]
+async fn fetch_jwks(&self, url: &Url) -> Result<Jwks> {
+	let body = self
+		.services
+		.client
+		.default
+		.get(url.clone())
+		.send()
+		.await?
+		.error_for_status()?
+		.bytes()
+		.await?;
+
+	serde_json::from_slice(&body)
+		.map_err(|e| err!(BadServerResponse(debug_warn!("Invalid JSON web key set: {e}"))))
+}
+
+fn parse(token: &str) -> Result<Token<'_>> {
+	let invalid = || err!(Request(Forbidden("Token is not a valid JWT.")));
+	let decode = |part: &str| {
+		general_purpose::URL_SAFE_NO_PAD
+			.decode(part)
+			.map_err(|_| invalid())
+	};
+
+	let (signed, signature) = token.rsplit_once('.').ok_or_else(invalid)?;
+	let (header, claims) = signed.split_once('.').ok_or_else(invalid)?;
+
+	Ok(Token {
+		header: serde_json::from_slice(&decode(header)?).map_err(|_| invalid())?,
+		claims: serde_json::from_slice(&decode(claims)?).map_err(|_| invalid())?,
+		signed,
+		signature: decode(signature)?,
+	})
+}
+
+fn verify_hmac(token: &Token<'_>, secret: &[u8]) -> Result {
+	let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+	hmac::verify(&key, token.signed.as_bytes(), &token.signature)
+		.map_err(|_| err!(Request(Forbidden("Invalid JWT signature."))))
+}
+
+fn verify_jwk(token: &Token<'_>, jwk: &Jwk) -> Result {
+	let decode = |part: Option<&String>| {
+		part.and_then(|part| general_purpose::URL_SAFE_NO_PAD.decode(part).ok())
+			.ok_or_else(|| err!(Request(Forbidden("JWT signing key is invalid."))))
+	};
+
+	let message = token.signed.as_bytes();
+	let verified = match token.header.alg.as_str() {
+		| "RS256" => signature::RsaPublicKeyComponents {
+			n: decode(jwk.n.as_ref())?,
+			e: decode(jwk.e.as_ref())?,
+		}
+		.verify(&signature::RSA_PKCS1_2048_8192_SHA256, message, &token.signature),
+		| "ES256" if jwk.crv.as_deref() == Some("P-256") => {
+			let mut point = vec![0x04];
+			point.extend(decode(jwk.x.as_ref())?);
+			point.extend(decode(jwk.y.as_ref())?);
+			signature::UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_FIXED, point)
+				.verify(message, &token.signature)
+		},
+		| _ => return Err!(Request(Forbidden("Unsupported JWT signing algorithm."))),
+	};
+
+	verified.map_err(|_| err!(Request(Forbidden("Invalid JWT signature."))))
+}
+
+fn check_validity(claims: &Claims, now: f64) -> Result {
+	if claims.exp.is_some_and(|exp| now >= exp) || claims.nbf.is_some_and(|nbf| now < nbf) {
+		return Err!(Request(Forbidden("Token has expired or is not yet valid.")));
+	}
+
+	Ok(())
+}
+
+fn key_type(alg: &str) -> &'static str {
+	match alg {
+		| "ES256" => "EC",
+		| _ => "RSA",
+	}
+}
@@ -0,0 +1,31 @@
+#![cfg(test)]
+
+const TOKEN: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+                     eyJzdWIiOiJhbGljZSIsImV4cCI6MTcwMDAwMDAwMC41LCJuYmYiOjE2MDAwMDAwMDB9.\
+                     PyPBdKMkPRmsf28UZoprRnDlep6Qdti6wcy7_xp-Llg";
+
+#[test]
+fn hs256_token() {
+	let token = super::parse(TOKEN).expect("token parses");
+
+	assert_eq!(token.header.alg, "HS256");
+	assert_eq!(token.claims.sub, "alice");
+	assert_eq!(token.claims.exp, Some(1_700_000_000.5), "numeric dates may be fractional");
+
+	super::verify_hmac(&token, b"secret").expect("signature matches the secret");
+	assert!(super::verify_hmac(&token, b"wrong").is_err(), "other secrets are rejected");
+
+	super::check_validity(&token.claims, 1_700_000_000.0).expect("valid before exp");
+	assert!(super::check_validity(&token.claims, 1_700_000_000.5).is_err(), "expired at exp");
+	assert!(
+		super::check_validity(&token.claims, 1_500_000_000.0).is_err(),
+		"invalid before nbf"
+	);
+}
+
+#[test]
+fn malformed_token() {
+	assert!(super::parse("").is_err());
+	assert!(super::parse("a.b").is_err());
+	assert!(super::parse("e30.e30.").is_err(), "claims without sub are rejected");
+}
@@ -1,4 +1,4 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{cmp::Reverse, collections::BTreeMap, sync::Arc};
 
 use conduwuit::{
 	err, implement,
@@ -93,6 +93,15 @@ pub async fn update_backup<'a>(
 		return Err!(Request(NotFound("Tried to update nonexistent backup.")));
 	}
 
+	if let Ok(existing) = self
+		.get_session(user_id, version, room_id, session_id)
+		.await
+	{
+		if !is_better_key(key_data, &existing) {
+			return Ok(());
+		}
+	}
+
 	let count = self.services.globals.next_count().unwrap();
 	self.db.backupid_etag.put(key, count);
 	self.db
@@ -169,6 +178,21 @@ pub async fn add_key(
 	Ok(())
 }
 
+/// Whether a session key should replace the one already in the backup. Verified
+/// keys win, then the lower first_message_index, then the lower
+/// forwarded_count; otherwise the existing key is kept.
+fn is_better_key(new: &Raw<KeyBackupData>, existing: &Raw<KeyBackupData>) -> bool {
+	let (Ok(new), Ok(existing)) = (new.deserialize(), existing.deserialize()) else {
+		return true;
+	};
+
+	let rank = |key: &KeyBackupData| {
+		(key.is_verified, Reverse(key.first_message_index), Reverse(key.forwarded_count))
+	};
+
+	rank(&new) > rank(&existing)
+}
+
 #[implement(Service)]
 pub async fn count_keys(&self, user_id: &UserId, version: &str) -> usize {
 	let prefix = (user_id, version);
@@ -0,0 +1,184 @@
+//! BlurHash Placeholders (MSC2448)
+//!
+//! This functionality is gated by 'media_thumbnail' which provides the image
+//! decoder; without it no blurhash is generated.
+
+#[cfg(feature = "media_thumbnail")]
+use std::f64::consts::PI;
+
+/// Number of horizontal and vertical components in generated blurhashes.
+#[cfg(feature = "media_thumbnail")]
+const COMPONENTS: (u32, u32) = (4, 3);
+
+/// Images are downscaled to fit these dimensions before being encoded.
+#[cfg(feature = "media_thumbnail")]
+const MAX_DIM: u32 = 64;
+
+#[cfg(feature = "media_thumbnail")]
+const BASE83: &[u8; 83] =
+	b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+/// Files larger than this are not decoded for a blurhash.
+#[cfg(feature = "media_thumbnail")]
+const MAX_FILE_LEN: usize = 16 * 1024 * 1024;
+
+/// Decoding an image for a blurhash may allocate at most this much.
+#[cfg(feature = "media_thumbnail")]
+const MAX_ALLOC: u64 = 256 * 1024 * 1024;
+
+/// Computes a blurhash for an image, if the file can be decoded as one within
+/// the size and allocation limits.
+#[cfg(feature = "media_thumbnail")]
+#[must_use]
+pub fn create_blurhash(file: &[u8]) -> Option<String> {
+	use std::io::Cursor;
+
+	use image::{ImageReader, Limits};
+
+	if file.len() > MAX_FILE_LEN {
+		return None;
+	}
+
+	let mut limits = Limits::default();
+	limits.max_alloc = Some(MAX_ALLOC);
+
+	let mut reader = ImageReader::new(Cursor::new(file))
+		.with_guessed_format()
+		.ok()?;
+
+	reader.limits(limits);
+	let image = reader.decode().ok()?;
+	let image = image.thumbnail(MAX_DIM, MAX_DIM).into_rgb8();
+
+	encode(COMPONENTS, image.width(), image.height(), image.as_raw())
+}
+
+#[cfg(not(feature = "media_thumbnail"))]
+#[must_use]
+pub fn create_blurhash(_file: &[u8]) -> Option<String> { None }
+
+/// Encodes packed 8-bit RGB pixels of an image with the given dimensions.
+#[cfg(feature = "media_thumbnail")]
+pub(super) fn encode(
+	(x_components, y_components): (u32, u32),
+	width: u32,
+	height: u32,
+	rgb: &[u8],
+) -> Option<String> {
+	if width == 0 || height == 0 {
+		return None;
+	}
+
+	let pixels: Vec<[f64; 3]> = rgb
+		.chunks_exact(3)
+		.map(|pixel| [pixel[0], pixel[1], pixel[2]].map(srgb_to_linear))
+		.collect();
+
+	let row_len = usize::try_from(width).ok()?;
+	let scale = 1.0 / (f64::from(width) * f64::from(height));
+	let mut factors = Vec::new();
+	for j in 0..y_components {
+		for i in 0..x_components {
+			let normalisation = if i == 0 && j == 0 { 1.0 } else { 2.0 };
+			let mut factor = [0.0_f64; 3];
+			for (y, row) in (0..height).zip(pixels.chunks_exact(row_len)) {
+				for (x, pixel) in (0..width).zip(row) {
+					let basis = normalisation
+						* (PI * f64::from(i) * f64::from(x) / f64::from(width)).cos()
+						* (PI * f64::from(j) * f64::from(y) / f64::from(height)).cos();
+
+					for (sum, value) in factor.iter_mut().zip(pixel) {
+						*sum = basis.mul_add(*value, *sum);
+					}
+				}
+			}
+
+			factors.push(factor.map(|sum| sum * scale));
+		}
+	}
+
+	let (dc, ac) = factors.split_first()?;
+	let size_flag = x_components
+		.saturating_sub(1)
+		.saturating_add(y_components.saturating_sub(1).saturating_mul(9));
+
+	let (quantised_max, max_value) = if ac.is_empty() {
+		(0.0, 1.0)
+	} else {
+		let actual_max = ac
+			.iter()
+			.flatten()
+			.fold(0.0_f64, |max, value| max.max(value.abs()));
+
+		let quantised_max = actual_max.mul_add(166.0, -0.5).floor().clamp(0.0, 82.0);
+		(quantised_max, (quantised_max + 1.0) / 166.0)
+	};
+
+	let mut hash = String::new();
+	encode_base83(&mut hash, size_flag, 1);
+	encode_base83(&mut hash, float_to_u32(quantised_max), 1);
+	encode_base83(&mut hash, encode_dc(dc), 4);
+	for factor in ac {
+		encode_base83(&mut hash, encode_ac(factor, max_value), 2);
+	}
+
+	Some(hash)
+}
+
+#[cfg(feature = "media_thumbnail")]
+fn encode_dc(factor: &[f64; 3]) -> u32 {
+	let [r, g, b] = factor.map(linear_to_srgb);
+	u32::from_be_bytes([0, r, g, b])
+}
+
+#[cfg(feature = "media_thumbnail")]
+fn encode_ac(factor: &[f64; 3], max_value: f64) -> u32 {
+	let [r, g, b] = factor.map(|value| {
+		let value = value / max_value;
+		value
+			.abs()
+			.sqrt()
+			.copysign(value)
+			.mul_add(9.0, 9.5)
+			.floor()
+			.clamp(0.0, 18.0)
+	});
+
+	float_to_u32(r.mul_add(19.0 * 19.0, g.mul_add(19.0, b)))
+}
+
+#[cfg(feature = "media_thumbnail")]
+fn encode_base83(hash: &mut String, value: u32, length: u32) {
+	for i in (0..length).rev() {
+		let digit = value.checked_div(83_u32.pow(i)).unwrap_or(0) % 83;
+		let digit = usize::try_from(digit).expect("base83 digit fits in usize");
+		hash.push(char::from(BASE83[digit]));
+	}
+}
+
+#[cfg(feature = "media_thumbnail")]
+fn srgb_to_linear(value: u8) -> f64 {
+	let value = f64::from(value) / 255.0;
+	if value <= 0.040_45 {
+		value / 12.92
+	} else {
+		((value + 0.055) / 1.055).powf(2.4)
+	}
+}
+
+#[cfg(feature = "media_thumbnail")]
+#[allow(clippy::as_conversions, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn linear_to_srgb(value: f64) -> u8 {
+	let value = value.clamp(0.0, 1.0);
+	let srgb = if value <= 0.003_130_8 {
+		value * 12.92
+	} else {
+		value.powf(1.0 / 2.4).mul_add(1.055, -0.055)
+	};
+
+	srgb.mul_add(255.0, 0.5) as u8
+}
+
+#[cfg(feature = "media_thumbnail")]
+#[allow(clippy::as_conversions, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn float_to_u32(value: f64) -> u32 { value as u32 }
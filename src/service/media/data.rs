@@ -5,16 +5,19 @@
 	utils::{str_from_bytes, stream::TryIgnore, string_from_bytes, ReadyExt},
 	Err, Result,
 };
-use database::{Database, Interfix, Map};
+use database::{Database, Deserialized, Interfix, Json, Map};
 use futures::StreamExt;
 use ruma::{http_headers::ContentDisposition, Mxc, OwnedMxcUri, UserId};
 
-use super::{preview::UrlPreviewData, thumbnail::Dim};
+use super::{preview::UrlPreviewData, quota::Usage, thumbnail::Dim};
 
 pub(crate) struct Data {
+	mediaid_authenticatedonly: Arc<Map>,
 	mediaid_file: Arc<Map>,
 	mediaid_user: Arc<Map>,
+	sha256_mediaid: Arc<Map>,
 	url_previews: Arc<Map>,
+	userid_uploadusage: Arc<Map>,
 }
 
 #[derive(Debug)]
@@ -27,9 +30,12 @@ pub(super) struct Metadata {
 impl Data {
 	pub(super) fn new(db: &Arc<Database>) -> Self {
 		Self {
+			mediaid_authenticatedonly: db["mediaid_authenticatedonly"].clone(),
 			mediaid_file: db["mediaid_file"].clone(),
 			mediaid_user: db["mediaid_user"].clone(),
+			sha256_mediaid: db["sha256_mediaid"].clone(),
 			url_previews: db["url_previews"].clone(),
+			userid_uploadusage: db["userid_uploadusage"].clone(),
 		}
 	}
 
@@ -78,6 +84,16 @@ pub(super) async fn delete_file_mxc(&self, mxc: &Mxc<'_>) {
 				self.mediaid_user.remove(key);
 			})
 			.await;
+
+		self.mediaid_authenticatedonly.del(mxc);
+	}
+
+	pub(super) fn set_authenticated_only(&self, mxc: &Mxc<'_>) {
+		self.mediaid_authenticatedonly.put_raw(mxc, []);
+	}
+
+	pub(super) async fn is_authenticated_only(&self, mxc: &Mxc<'_>) -> bool {
+		self.mediaid_authenticatedonly.qry(mxc).await.is_ok()
 	}
 
 	/// Searches for all files with the given MXC
@@ -154,6 +170,31 @@ pub(super) async fn get_all_user_mxcs(&self, user_id: &UserId) -> Vec<OwnedMxcUr
 			.await
 	}
 
+	/// Records the file with the given content hash stored for a media key.
+	pub(super) fn set_content_hash(&self, digest: &[u8], key: &[u8]) {
+		self.sha256_mediaid.insert(digest, key);
+	}
+
+	pub(super) fn remove_content_hash(&self, digest: &[u8]) {
+		self.sha256_mediaid.remove(digest);
+	}
+
+	/// Finds a media key whose file has the given content hash.
+	pub(super) async fn search_content_hash(&self, digest: &[u8]) -> Result<Vec<u8>> {
+		self.sha256_mediaid
+			.get(digest)
+			.await
+			.map(|key| key.to_vec())
+	}
+
+	pub(super) async fn get_upload_usage(&self, user: &UserId) -> Result<Usage> {
+		self.userid_uploadusage.get(user).await.deserialized()
+	}
+
+	pub(super) fn set_upload_usage(&self, user: &UserId, usage: Usage) {
+		self.userid_uploadusage.raw_put(user, Json(usage));
+	}
+
 	/// Gets all the media keys in our database (this includes all the metadata
 	/// associated with it such as width, height, content-type, etc)
 	pub(crate) async fn get_all_media_keys(&self) -> Vec<Vec<u8>> {
@@ -212,17 +253,26 @@ pub(super) fn set_url_preview(
 		Ok(())
 	}
 
-	pub(super) async fn get_url_preview(&self, url: &str) -> Result<UrlPreviewData> {
+	/// Returns the cached preview for the URL, unless it was cached before
+	/// `not_before` (seconds since the unix epoch).
+	pub(super) async fn get_url_preview(
+		&self,
+		url: &str,
+		not_before: Duration,
+	) -> Result<UrlPreviewData> {
 		let values = self.url_previews.get(url).await?;
 
 		let mut values = values.split(|&b| b == 0xFF);
 
-		let _ts = values.next();
-		/* if we ever decide to use timestamp, this is here.
-		match values.next().map(|b| u64::from_be_bytes(b.try_into().expect("valid BE array"))) {
-			Some(0) => None,
-			x => x,
-		};*/
+		let timestamp = values
+			.next()
+			.and_then(|b| b.try_into().ok())
+			.map(u64::from_be_bytes)
+			.unwrap_or_default();
+
+		if timestamp < not_before.as_secs() {
+			return Err!(Request(NotFound("Cached URL preview has expired.")));
+		}
 
 		let title = match values
 			.next()
@@ -1,11 +1,17 @@
+mod blurhash;
 mod data;
 pub(super) mod migrations;
 mod preview;
+mod quota;
 mod remote;
 mod tests;
 mod thumbnail;
 
-use std::{path::PathBuf, sync::Arc, time::SystemTime};
+use std::{
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::SystemTime,
+};
 
 use async_trait::async_trait;
 use base64::{engine::general_purpose, Engine as _};
@@ -14,14 +20,14 @@
 	utils::{self, MutexMap},
 	warn, Err, Result, Server,
 };
-use ruma::{http_headers::ContentDisposition, Mxc, OwnedMxcUri, UserId};
+use ruma::{http_headers::ContentDisposition, Mxc, OwnedMxcUri, OwnedUserId, UserId};
 use tokio::{
 	fs,
 	io::{AsyncReadExt, AsyncWriteExt, BufReader},
 };
 
 use self::data::{Data, Metadata};
-pub use self::thumbnail::Dim;
+pub use self::{blurhash::create_blurhash, thumbnail::Dim};
 use crate::{client, globals, sending, Dep};
 
 #[derive(Debug)]
@@ -33,6 +39,7 @@ pub struct FileMeta {
 
 pub struct Service {
 	url_preview_mutex: MutexMap<String, ()>,
+	upload_quota_mutex: MutexMap<OwnedUserId, ()>,
 	pub(super) db: Data,
 	services: Services,
 }
@@ -58,6 +65,7 @@ impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			url_preview_mutex: MutexMap::new(),
+			upload_quota_mutex: MutexMap::new(),
 			db: Data::new(args.db),
 			services: Services {
 				server: args.server.clone(),
@@ -97,10 +105,18 @@ pub async fn create(
 		)?;
 
 		//TODO: Dangling metadata in database if creation fails
-		let mut f = self.create_media_file(&key).await?;
-		f.write_all(file).await?;
+		self.create_media_file_dedup(&key, file).await
+	}
 
-		Ok(())
+	/// Restricts media to the authenticated media endpoints (MSC3916); the
+	/// legacy unauthenticated endpoints will no longer serve it.
+	#[inline]
+	pub fn set_authenticated_only(&self, mxc: &Mxc<'_>) { self.db.set_authenticated_only(mxc); }
+
+	/// Whether media may only be served by the authenticated media endpoints.
+	#[inline]
+	pub async fn is_authenticated_only(&self, mxc: &Mxc<'_>) -> bool {
+		self.db.is_authenticated_only(mxc).await
 	}
 
 	/// Deletes a file in the database and from the media directory via an MXC
@@ -110,6 +126,10 @@ pub async fn delete(&self, mxc: &Mxc<'_>) -> Result<()> {
 				trace!(?mxc, "MXC Key: {key:?}");
 				debug_info!(?mxc, "Deleting from filesystem");
 
+				if let Err(e) = self.remove_content_hash(&key).await {
+					debug_error!(?mxc, "Failed to remove media content hash: {e}");
+				}
+
 				if let Err(e) = self.remove_media_file(&key).await {
 					debug_error!(?mxc, "Failed to remove media file: {e}");
 				}
@@ -355,22 +375,81 @@ async fn remove_media_file(&self, key: &[u8]) -> Result<()> {
 		Ok(file_rm?)
 	}
 
+	/// Stores a file, hard linking it to an existing file with identical
+	/// content instead when possible. The filesystem's link count then serves
+	/// as the reference count, and a file is only freed once every MXC using
+	/// it has been deleted.
+	async fn create_media_file_dedup(&self, key: &[u8], file: &[u8]) -> Result<()> {
+		let digest = <sha2::Sha256 as sha2::Digest>::digest(file);
+
+		if let Ok(existing) = self.db.search_content_hash(&digest).await {
+			let existing = self.get_media_file(&existing);
+			let path = self.get_media_file(key);
+			match fs::hard_link(&existing, &path).await {
+				| Ok(()) => {
+					debug!(?key, ?path, ?existing, "Linked media file with identical content");
+					self.create_legacy_link(key, &path).await;
+					return Ok(());
+				},
+				| Err(e) => {
+					debug_warn!(?key, ?existing, "Failed to link media file, copying: {e}");
+				},
+			}
+		}
+
+		let mut f = self.create_media_file(key).await?;
+		f.write_all(file).await?;
+		self.db.set_content_hash(&digest, key);
+
+		Ok(())
+	}
+
+	/// Forgets the content hash of a file if it was recorded for `key`, so new
+	/// uploads aren't linked to a file which is being deleted.
+	async fn remove_content_hash(&self, key: &[u8]) -> Result<()> {
+		let file = fs::read(self.get_media_file(key)).await?;
+		let digest = <sha2::Sha256 as sha2::Digest>::digest(&file);
+
+		if self
+			.db
+			.search_content_hash(&digest)
+			.await
+			.is_ok_and(|existing| existing == key)
+		{
+			self.db.remove_content_hash(&digest);
+		}
+
+		Ok(())
+	}
+
 	async fn create_media_file(&self, key: &[u8]) -> Result<fs::File> {
 		let path = self.get_media_file(key);
 		debug!(?key, ?path, "Creating media file");
 
+		// The path may be a hard link shared with other media, which must not be
+		// truncated in place.
+		if let Err(e) = fs::remove_file(&path).await {
+			if e.kind() != std::io::ErrorKind::NotFound {
+				return Err(e.into());
+			}
+		}
+
 		let file = fs::File::create(&path).await?;
+		self.create_legacy_link(key, &path).await;
+
+		Ok(file)
+	}
+
+	async fn create_legacy_link(&self, key: &[u8], path: &Path) {
 		if self.services.server.config.media_compat_file_link {
 			let legacy = self.get_media_file_b64(key);
-			if let Err(e) = fs::symlink(&path, &legacy).await {
+			if let Err(e) = fs::symlink(path, &legacy).await {
 				debug_error!(
 					key = ?encode_key(key), ?path, ?legacy,
 					"Failed to create legacy media symlink: {e}"
 				);
 			}
 		}
-
-		Ok(file)
 	}
 
 	#[inline]
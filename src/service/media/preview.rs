@@ -5,7 +5,7 @@
 //! of dependencies and nulls out results through the existing interface when
 //! not featured.
 
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use conduwuit::{debug, Err, Result};
 use conduwuit_core::implement;
@@ -47,14 +47,21 @@ pub async fn set_url_preview(&self, url: &str, data: &UrlPreviewData) -> Result<
 
 #[implement(Service)]
 pub async fn get_url_preview(&self, url: &Url) -> Result<UrlPreviewData> {
-	if let Ok(preview) = self.db.get_url_preview(url.as_str()).await {
+	let ttl = Duration::from_secs(self.services.server.config.url_preview_cache_ttl);
+	let not_before = SystemTime::now()
+		.checked_sub(ttl)
+		.unwrap_or(SystemTime::UNIX_EPOCH)
+		.duration_since(SystemTime::UNIX_EPOCH)
+		.unwrap_or_default();
+
+	if let Ok(preview) = self.db.get_url_preview(url.as_str(), not_before).await {
 		return Ok(preview);
 	}
 
 	// ensure that only one request is made per URL
 	let _request_lock = self.url_preview_mutex.lock(url.as_str()).await;
 
-	match self.db.get_url_preview(url.as_str()).await {
+	match self.db.get_url_preview(url.as_str(), not_before).await {
 		| Ok(preview) => Ok(preview),
 		| Err(_) => self.request_url_preview(url).await,
 	}
@@ -269,7 +276,7 @@ pub fn url_preview_allowed(&self, url: &Url) -> bool {
 							 url_preview_domain_explicit_denylist (check 1/3)",
 							&root_domain
 						);
-						return true;
+						return false;
 					}
 
 					if allowlist_domain_explicit.contains(&root_domain.to_owned()) {
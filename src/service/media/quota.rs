@@ -0,0 +1,67 @@
+use conduwuit::{implement, utils, Err, Result};
+use ruma::UserId;
+use serde::{Deserialize, Serialize};
+
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+/// Bytes uploaded by a user on a given day.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub(super) struct Usage {
+	day: u64,
+	bytes: usize,
+}
+
+/// Counts an upload towards the user's daily upload quota, failing with
+/// M_TOO_LARGE if it would exceed `max_upload_size_per_day`. Uploads which
+/// fail after this have to be given back with `refund_upload_quota`.
+#[implement(super::Service)]
+pub async fn use_upload_quota(&self, user: &UserId, size: usize) -> Result<()> {
+	let Some(max) = self.services.server.config.max_upload_size_per_day else {
+		return Ok(());
+	};
+
+	let _lock = self.upload_quota_mutex.lock(user).await;
+	let usage = self.upload_usage_today(user).await;
+	let bytes = usage.bytes.saturating_add(size);
+	if bytes > max {
+		return Err!(Request(TooLarge("Daily upload quota of {max} bytes exceeded.")));
+	}
+
+	self.db
+		.set_upload_usage(user, Usage { day: usage.day, bytes });
+
+	Ok(())
+}
+
+/// Gives back quota used by an upload which failed.
+#[implement(super::Service)]
+pub async fn refund_upload_quota(&self, user: &UserId, size: usize) {
+	if self
+		.services
+		.server
+		.config
+		.max_upload_size_per_day
+		.is_none()
+	{
+		return;
+	}
+
+	let _lock = self.upload_quota_mutex.lock(user).await;
+	let usage = self.upload_usage_today(user).await;
+	let bytes = usage.bytes.saturating_sub(size);
+
+	self.db
+		.set_upload_usage(user, Usage { day: usage.day, bytes });
+}
+
+#[implement(super::Service)]
+async fn upload_usage_today(&self, user: &UserId) -> Usage {
+	let today = utils::millis_since_unix_epoch() / DAY_MILLIS;
+
+	self.db
+		.get_upload_usage(user)
+		.await
+		.ok()
+		.filter(|usage| usage.day == today)
+		.unwrap_or(Usage { day: today, bytes: 0 })
+}
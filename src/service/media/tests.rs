@@ -103,3 +103,20 @@ fn get_url_preview(&self, _url: &str) -> Option<UrlPreviewData> { todo!() }
 		r.to_str().unwrap().len()
 	);
 }
+
+#[test]
+#[cfg(feature = "media_thumbnail")]
+fn blurhash_encode() {
+	let rgb = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+
+	assert_eq!(
+		super::blurhash::encode((4, 3), 2, 2, &rgb).as_deref(),
+		Some("L~Lqe9|ldL|l~h|c_X|cfH|T|c|T"),
+		"blurhash should match the reference implementation"
+	);
+	assert_eq!(
+		super::blurhash::encode((4, 3), 0, 0, &[]),
+		None,
+		"empty images have no blurhash"
+	);
+}
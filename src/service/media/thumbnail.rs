@@ -24,6 +24,15 @@ pub struct Dim {
 	pub method: Method,
 }
 
+/// The sizes thumbnails are normalized to, see `Dim::normalized`.
+const THUMBNAIL_SIZES: [(u32, u32, Method); 5] = [
+	(32, 32, Method::Crop),
+	(96, 96, Method::Crop),
+	(320, 240, Method::Scale),
+	(640, 480, Method::Scale),
+	(800, 600, Method::Scale),
+];
+
 impl super::Service {
 	/// Uploads or replaces a file thumbnail.
 	#[allow(clippy::too_many_arguments)]
@@ -73,6 +82,18 @@ pub async fn get_thumbnail(&self, mxc: &Mxc<'_>, dim: &Dim) -> Result<Option<Fil
 			Ok(None)
 		}
 	}
+
+	/// Generates and stores thumbnails of a file in every normalized size, so
+	/// they don't have to be generated when first requested.
+	#[tracing::instrument(skip(self), name = "pregenerate", level = "debug")]
+	pub async fn pregenerate_thumbnails(&self, mxc: &Mxc<'_>) -> Result<()> {
+		for (width, height, method) in THUMBNAIL_SIZES {
+			self.get_thumbnail(mxc, &Dim::new(width, height, Some(method)))
+				.await?;
+		}
+
+		Ok(())
+	}
 }
 
 /// Using saved thumbnail
@@ -237,14 +258,12 @@ pub fn scaled(&self, image: &Self) -> Result<Self> {
 	/// Ignores the input Method.
 	#[must_use]
 	pub fn normalized(&self) -> Self {
-		match (self.width, self.height) {
-			| (0..=32, 0..=32) => Self::new(32, 32, Some(Method::Crop)),
-			| (0..=96, 0..=96) => Self::new(96, 96, Some(Method::Crop)),
-			| (0..=320, 0..=240) => Self::new(320, 240, Some(Method::Scale)),
-			| (0..=640, 0..=480) => Self::new(640, 480, Some(Method::Scale)),
-			| (0..=800, 0..=600) => Self::new(800, 600, Some(Method::Scale)),
-			| _ => Self::default(),
-		}
+		THUMBNAIL_SIZES
+			.into_iter()
+			.find(|&(width, height, _)| self.width <= width && self.height <= height)
+			.map_or_else(Self::default, |(width, height, method)| {
+				Self::new(width, height, Some(method))
+			})
 	}
 
 	/// Returns true if the method is Crop.
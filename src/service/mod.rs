@@ -10,9 +10,11 @@
 pub mod appservice;
 pub mod client;
 pub mod config;
+pub mod email;
 pub mod emergency;
 pub mod federation;
 pub mod globals;
+pub mod jwt;
 pub mod key_backups;
 pub mod media;
 pub mod presence;
@@ -21,6 +23,8 @@
 pub mod rooms;
 pub mod sending;
 pub mod server_keys;
+pub mod server_notices;
+pub mod sso;
 pub mod sync;
 pub mod transaction_ids;
 pub mod uiaa;
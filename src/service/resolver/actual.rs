@@ -11,7 +11,7 @@
 
 use super::{
 	cache::{CachedDest, CachedOverride, MAX_IPS},
-	fed::{add_port_to_hostname, get_ip_with_port, FedDest, PortString},
+	fed::{add_port_to_hostname, get_ip_literal, get_ip_with_port, FedDest, PortString},
 };
 
 #[derive(Clone, Debug)]
@@ -84,8 +84,13 @@ pub async fn resolve_actual_dest(
 		// to an IP address if it wasn't specified
 		let host = if let Ok(addr) = host.parse::<SocketAddr>() {
 			FedDest::Literal(addr)
-		} else if let Ok(addr) = host.parse::<IpAddr>() {
-			FedDest::Named(addr.to_string(), FedDest::default_port())
+		} else if let Some(addr) = get_ip_literal(&host) {
+			let addr = match addr {
+				| IpAddr::V4(addr) => addr.to_string(),
+				| IpAddr::V6(addr) => format!("[{addr}]"),
+			};
+
+			FedDest::Named(addr, FedDest::default_port())
 		} else if let Some(pos) = host.find(':') {
 			let (host, port) = host.split_at(pos);
 			FedDest::Named(
@@ -22,13 +22,24 @@ pub enum FedDest {
 pub(crate) fn get_ip_with_port(dest_str: &str) -> Option<FedDest> {
 	if let Ok(dest) = dest_str.parse::<SocketAddr>() {
 		Some(FedDest::Literal(dest))
-	} else if let Ok(ip_addr) = dest_str.parse::<IpAddr>() {
+	} else if let Some(ip_addr) = get_ip_literal(dest_str) {
 		Some(FedDest::Literal(SocketAddr::new(ip_addr, 8448)))
 	} else {
 		None
 	}
 }
 
+/// Parses an IP literal without a port; IPv6 literals may be given in the
+/// bracketed form used by server names.
+pub(crate) fn get_ip_literal(dest_str: &str) -> Option<IpAddr> {
+	dest_str
+		.strip_prefix('[')
+		.and_then(|dest| dest.strip_suffix(']'))
+		.unwrap_or(dest_str)
+		.parse()
+		.ok()
+}
+
 pub(crate) fn add_port_to_hostname(dest: &str) -> FedDest {
 	let (host, port) = match dest.find(':') {
 		| None => (dest, DEFAULT_PORT),
@@ -1,6 +1,6 @@
 #![cfg(test)]
 
-use super::fed::{add_port_to_hostname, get_ip_with_port, FedDest};
+use super::fed::{add_port_to_hostname, get_ip_literal, get_ip_with_port, FedDest};
 
 #[test]
 fn ips_get_default_ports() {
@@ -14,6 +14,22 @@ fn ips_get_default_ports() {
 	);
 }
 
+#[test]
+fn bracketed_ips_get_default_ports() {
+	assert_eq!(
+		get_ip_with_port("[dead:beef::]"),
+		Some(FedDest::Literal("[dead:beef::]:8448".parse().unwrap()))
+	);
+}
+
+#[test]
+fn ip_literals_without_ports() {
+	assert_eq!(get_ip_literal("1.1.1.1"), Some("1.1.1.1".parse().unwrap()));
+	assert_eq!(get_ip_literal("[dead::beef]"), Some("dead::beef".parse().unwrap()));
+	assert_eq!(get_ip_literal("1.1.1.1:1234"), None);
+	assert_eq!(get_ip_literal("example.com"), None);
+}
+
 #[test]
 fn ips_keep_custom_ports() {
 	assert_eq!(
@@ -4,10 +4,15 @@
 	RoomId, ServerName,
 };
 
-/// Returns Ok if the acl allows the server
+/// Returns Ok if the room federates and its acl allows the server
 #[implement(super::Service)]
 #[tracing::instrument(skip_all, level = "debug")]
 pub async fn acl_check(&self, server_name: &ServerName, room_id: &RoomId) -> Result {
+	if !self.services.state_accessor.is_federated(room_id).await {
+		debug!("Server {server_name} denied access to unfederated room {room_id}");
+		return Err!(Request(Forbidden("Room does not federate")));
+	}
+
 	let Ok(acl_event_content) = self
 		.services
 		.state_accessor
@@ -40,6 +40,19 @@ pub(super) async fn fetch_prev(
 	while let Some(prev_event_id) = todo_outlier_stack.pop_front() {
 		self.services.server.check_running()?;
 
+		// The same event may be queued more than once when it is referenced by
+		// several events at the same depth; only fetch it the first time.
+		if graph.contains_key(&prev_event_id) {
+			continue;
+		}
+
+		let limit = self.services.server.config.max_fetch_prev_events;
+		if amount > limit {
+			debug_warn!("Max prev event limit reached! Limit: {limit}");
+			graph.insert(prev_event_id.clone(), HashSet::new());
+			continue;
+		}
+
 		if let Some((pdu, mut json_opt)) = self
 			.fetch_and_handle_outliers(origin, &[prev_event_id.clone()], create_event, room_id)
 			.boxed()
@@ -48,13 +61,6 @@ pub(super) async fn fetch_prev(
 		{
 			check_room_id(room_id, &pdu)?;
 
-			let limit = self.services.server.config.max_fetch_prev_events;
-			if amount > limit {
-				debug_warn!("Max prev event limit reached! Limit: {limit}");
-				graph.insert(prev_event_id.clone(), HashSet::new());
-				continue;
-			}
-
 			if json_opt.is_none() {
 				json_opt = self
 					.services
@@ -3,7 +3,10 @@
 	sync::Arc,
 };
 
-use conduwuit::{debug, debug_info, err, implement, trace, warn, Err, Error, PduEvent, Result};
+use conduwuit::{
+	debug, debug_info, err, implement, pdu::check_pdu_limits, trace, warn, Err, Error, PduEvent,
+	Result,
+};
 use futures::{future::ready, TryFutureExt};
 use ruma::{
 	api::client::error::ErrorKind,
@@ -28,11 +31,16 @@ pub(super) async fn handle_outlier_pdu<'a>(
 	// 1. Remove unsigned field
 	value.remove("unsigned");
 
-	// TODO: For RoomVersion6 we must check that Raw<..> is canonical do we anywhere?: https://matrix.org/docs/spec/rooms/v6#canonical-json
+	// Reject events exceeding the size and field limits from the spec
+	check_pdu_limits(&value)?;
+
+	// Canonical JSON is enforced when the event is parsed by
+	// gen_event_id_canonical_json(): https://spec.matrix.org/latest/appendices/#canonical-json
 
 	// 2. Check signatures, otherwise drop
 	// 3. check content hash, redact if doesn't match
 	let room_version_id = get_room_version_id(create_event)?;
+	let room_version = to_room_version(&room_version_id)?;
 	let mut val = match self
 		.services
 		.server_keys
@@ -138,10 +146,17 @@ pub(super) async fn handle_outlier_pdu<'a>(
 		ready(auth_events.get(&key))
 	};
 
+	let third_party_invite = incoming_pdu
+		.third_party_invite_token()
+		.and_then(|token| {
+			auth_events.get(&StateEventType::RoomThirdPartyInvite.with_state_key(token))
+		})
+		.map(AsRef::as_ref);
+
 	let auth_check = state_res::event_auth::auth_check(
-		&to_room_version(&room_version_id),
+		&room_version,
 		&incoming_pdu,
-		None, // TODO: third party invite
+		third_party_invite,
 		state_fetch,
 	)
 	.await
@@ -18,6 +18,7 @@
 };
 
 use conduwuit::{
+	err,
 	utils::{MutexMap, TryFutureExtExt},
 	Err, PduEvent, Result, Server,
 };
@@ -132,6 +133,10 @@ fn get_room_version_id(create_event: &PduEvent) -> Result<RoomVersionId> {
 }
 
 #[inline]
-fn to_room_version(room_version_id: &RoomVersionId) -> RoomVersion {
-	RoomVersion::new(room_version_id).expect("room version is supported")
+fn to_room_version(room_version_id: &RoomVersionId) -> Result<RoomVersion> {
+	RoomVersion::new(room_version_id).map_err(|_| {
+		err!(Request(UnsupportedRoomVersion(
+			"Room version {room_version_id} is not supported."
+		)))
+	})
 }
@@ -2,6 +2,8 @@
 use ruma::{CanonicalJsonObject, CanonicalJsonValue, OwnedEventId, OwnedRoomId};
 use serde_json::value::RawValue as RawJsonValue;
 
+use crate::server_keys::BLOCKING_EVENT_LEN;
+
 type Parsed = (OwnedRoomId, OwnedEventId, CanonicalJsonObject);
 
 #[implement(super::Service)]
@@ -23,9 +25,18 @@ pub async fn parse_incoming_pdu(&self, pdu: &RawJsonValue) -> Result<Parsed> {
 		.await
 		.map_err(|_| err!("Server is not in room {room_id}"))?;
 
-	let (event_id, value) = gen_event_id_canonical_json(pdu, &room_version_id).map_err(|e| {
-		err!(Request(InvalidParam("Could not convert event to canonical json: {e}")))
-	})?;
+	let (event_id, value) = if pdu.get().len() < BLOCKING_EVENT_LEN {
+		gen_event_id_canonical_json(pdu, &room_version_id)
+	} else {
+		// Reference hashing large events is CPU-bound; keep it off the executor.
+		let pdu = pdu.to_owned();
+		self.services
+			.server
+			.runtime()
+			.spawn_blocking(move || gen_event_id_canonical_json(&pdu, &room_version_id))
+			.await?
+	}
+	.map_err(|e| err!(Request(InvalidParam("Could not convert event to canonical json: {e}"))))?;
 
 	Ok((room_id, event_id, value))
 }
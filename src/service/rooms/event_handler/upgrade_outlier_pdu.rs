@@ -70,7 +70,7 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 
 	let state_at_incoming_event =
 		state_at_incoming_event.expect("we always set this to some above");
-	let room_version = to_room_version(&room_version_id);
+	let room_version = to_room_version(&room_version_id)?;
 
 	debug!("Performing auth check");
 	// 11. Check the auth of the event passes based on the state of the event
@@ -82,10 +82,15 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 		self.services.timeline.get_pdu(event_id).await.ok()
 	};
 
+	let third_party_invite = match incoming_pdu.third_party_invite_token() {
+		| Some(token) => state_fetch(&StateEventType::RoomThirdPartyInvite, token).await,
+		| None => None,
+	};
+
 	let auth_check = state_res::event_auth::auth_check(
 		&room_version,
-		&incoming_pdu,
-		None, // TODO: third party invite
+		&*incoming_pdu,
+		third_party_invite.as_ref(),
 		|k, s| state_fetch(k, s.to_owned()),
 	)
 	.await
@@ -113,10 +118,14 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 		ready(auth_events.get(&key).cloned())
 	};
 
+	let third_party_invite = incoming_pdu.third_party_invite_token().and_then(|token| {
+		auth_events.get(&StateEventType::RoomThirdPartyInvite.with_state_key(token))
+	});
+
 	let auth_check = state_res::event_auth::auth_check(
 		&room_version,
-		&incoming_pdu,
-		None, // third-party invite
+		&*incoming_pdu,
+		third_party_invite,
 		state_fetch,
 	)
 	.await
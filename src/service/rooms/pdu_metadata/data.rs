@@ -76,18 +76,42 @@ pub(super) fn get_relations<'a>(
 		.map(|to_from| u64_from_u8(&to_from[8..16]))
 		.map(PduCount::from_unsigned)
 		.wide_filter_map(move |shorteventid| async move {
-			let pdu_id: RawPduId = PduId { shortroomid, shorteventid }.into();
-
-			let mut pdu = self.services.timeline.get_pdu_from_id(&pdu_id).await.ok()?;
-
-			if pdu.sender != user_id {
-				pdu.remove_transaction_id().log_err().ok();
-			}
+			let pdu = self
+				.get_relation(user_id, shortroomid, shorteventid)
+				.await?;
 
 			Some((shorteventid, pdu))
 		})
 	}
 
+	/// Relations targeting the event, oldest first.
+	pub(super) async fn get_target_relations(&self, target: ShortEventId) -> Vec<ShortEventId> {
+		let prefix = target.to_be_bytes();
+		self.tofrom_relation
+			.raw_keys_prefix(&prefix)
+			.ignore_err()
+			.map(|to_from| u64_from_u8(&to_from[8..16]))
+			.collect()
+			.await
+	}
+
+	pub(super) async fn get_relation(
+		&self,
+		user_id: &UserId,
+		shortroomid: ShortRoomId,
+		shorteventid: PduCount,
+	) -> Option<PduEvent> {
+		let pdu_id: RawPduId = PduId { shortroomid, shorteventid }.into();
+
+		let mut pdu = self.services.timeline.get_pdu_from_id(&pdu_id).await.ok()?;
+
+		if pdu.sender != user_id {
+			pdu.remove_transaction_id().log_err().ok();
+		}
+
+		Some(pdu)
+	}
+
 	#[inline]
 	pub(super) fn mark_as_referenced<'a, I>(&self, room_id: &RoomId, event_ids: I)
 	where
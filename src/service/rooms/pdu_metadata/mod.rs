@@ -1,18 +1,28 @@
 mod data;
-use std::sync::Arc;
-
-use conduwuit::{PduCount, Result};
-use futures::StreamExt;
-use ruma::{api::Direction, EventId, RoomId, UserId};
+use std::{
+	collections::{BTreeMap, HashSet},
+	sync::Arc,
+};
+
+use conduwuit::{
+	utils::{stream::WidebandExt, IterStream, ReadyExt},
+	PduCount, PduEvent, Result,
+};
+use futures::{FutureExt, StreamExt};
+use ruma::{api::Direction, events::relation::RelationType, EventId, RoomId, UserId};
+use serde_json::json;
 
 use self::data::{Data, PdusIterItem};
-use crate::{rooms, Dep};
+use crate::{rooms, rooms::short::ShortRoomId, Dep};
 
 pub struct Service {
 	services: Services,
 	db: Data,
 }
 
+/// Upper bound on the relations considered when bundling aggregations.
+const BUNDLED_RELATIONS_LIMIT: usize = 100;
+
 struct Services {
 	short: Dep<rooms::short::Service>,
 	timeline: Dep<rooms::timeline::Service>,
@@ -98,6 +108,108 @@ pub async fn get_relations(
 		pdus
 	}
 
+	/// Adds the server-side aggregations of the events' relations to their
+	/// `unsigned` `m.relations` objects. Each event's relations are found with
+	/// a lookup on its own key, so only events with relations cost more.
+	#[tracing::instrument(skip(self, pdus), level = "debug")]
+	pub async fn add_bundled_aggregations(
+		&self,
+		user_id: &UserId,
+		room_id: &RoomId,
+		pdus: &mut [PdusIterItem],
+	) -> Result {
+		// TODO: Support backfilled relations
+		let targets: HashSet<_> = pdus
+			.iter()
+			.filter(|(_, pdu)| !pdu.is_redacted())
+			.filter_map(|(count, _)| match count {
+				| PduCount::Normal(count) => Some(*count),
+				| PduCount::Backfilled(_) => None,
+			})
+			.collect();
+
+		let relations: BTreeMap<_, Vec<_>> = targets
+			.into_iter()
+			.stream()
+			.wide_then(|target| {
+				self.db
+					.get_target_relations(target)
+					.map(move |relations| (target, relations))
+			})
+			.ready_filter(|(_, relations)| !relations.is_empty())
+			.collect()
+			.await;
+
+		if relations.is_empty() {
+			return Ok(());
+		}
+
+		let shortroomid = self.services.short.get_shortroomid(room_id).await?;
+		for (count, pdu) in pdus {
+			let PduCount::Normal(target) = *count else {
+				continue;
+			};
+
+			if let Some(relations) = relations.get(&target).filter(|_| !pdu.is_redacted()) {
+				self.bundle_relations(user_id, shortroomid, pdu, relations)
+					.await?;
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Adds the aggregations of the given relations, oldest first, to the
+	/// event. References are bundled from the newest `BUNDLED_RELATIONS_LIMIT`
+	/// relations, but the latest edit is looked up among all of them.
+	async fn bundle_relations(
+		&self,
+		user_id: &UserId,
+		shortroomid: ShortRoomId,
+		pdu: &mut PduEvent,
+		relations: &[u64],
+	) -> Result {
+		let mut references = Vec::new();
+		let mut replacement = None;
+		for (i, &relation) in relations.iter().rev().enumerate() {
+			let capped = i >= BUNDLED_RELATIONS_LIMIT;
+			if capped && replacement.is_some() {
+				break;
+			}
+
+			let shorteventid = PduCount::from_unsigned(relation);
+			let Some(relation) = self
+				.db
+				.get_relation(user_id, shortroomid, shorteventid)
+				.await
+			else {
+				continue;
+			};
+
+			if !capped && relation.relation_type_equal(&RelationType::Reference) {
+				references.push(json!({ "event_id": relation.event_id }));
+			}
+
+			// Relations are visited newest first; only the original sender may edit
+			if replacement.is_none()
+				&& relation.sender == pdu.sender
+				&& relation.relation_type_equal(&RelationType::Replacement)
+			{
+				replacement = Some(relation.to_message_like_event());
+			}
+		}
+
+		if !references.is_empty() {
+			pdu.add_relation("m.reference", &json!({ "chunk": references }))?;
+		}
+
+		if let Some(replacement) = replacement {
+			pdu.add_relation("m.replace", &replacement)?;
+		}
+
+		Ok(())
+	}
+
 	#[tracing::instrument(skip_all, level = "debug")]
 	pub fn mark_as_referenced<'a, I>(&self, room_id: &RoomId, event_ids: I)
 	where
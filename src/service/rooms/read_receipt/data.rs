@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
 use conduwuit::{
 	utils::{stream::TryIgnore, ReadyExt},
@@ -7,7 +7,10 @@
 use database::{Deserialized, Json, Map};
 use futures::{Stream, StreamExt};
 use ruma::{
-	events::{receipt::ReceiptEvent, AnySyncEphemeralRoomEvent},
+	events::{
+		receipt::{ReceiptEvent, ReceiptThread},
+		AnySyncEphemeralRoomEvent,
+	},
 	serde::Raw,
 	CanonicalJsonObject, RoomId, UserId,
 };
@@ -46,13 +49,21 @@ pub(super) async fn readreceipt_update(
 		room_id: &RoomId,
 		event: &ReceiptEvent,
 	) {
-		// Remove old entry
+		// Remove the old entry of the same thread; users have a receipt per thread
+		let thread = receipt_thread(event, user_id);
 		let last_possible_key = (room_id, u64::MAX);
 		self.readreceiptid_readreceipt
-			.rev_keys_from_raw(&last_possible_key)
+			.rev_stream_from_raw(&last_possible_key)
 			.ignore_err()
-			.ready_take_while(|key| key.starts_with(room_id.as_bytes()))
-			.ready_filter_map(|key| key.ends_with(user_id.as_bytes()).then_some(key))
+			.ready_take_while(|(key, _)| key.starts_with(room_id.as_bytes()))
+			.ready_filter(|(key, _)| key.ends_with(user_id.as_bytes()))
+			.ready_filter_map(|(key, val)| {
+				let old_thread = serde_json::from_slice(val)
+					.map(|old| receipt_thread(&old, user_id))
+					.unwrap_or_default();
+
+				(old_thread == thread).then_some(key)
+			})
 			.ready_for_each(|key| self.readreceiptid_readreceipt.del(key))
 			.await;
 
@@ -116,3 +127,15 @@ pub(super) async fn last_privateread_update(
 			.unwrap_or(0)
 	}
 }
+
+/// The thread of the user's receipt in the event.
+fn receipt_thread(event: &ReceiptEvent, user_id: &UserId) -> ReceiptThread {
+	event
+		.content
+		.0
+		.values()
+		.flat_map(BTreeMap::values)
+		.find_map(|receipts| receipts.get(user_id))
+		.map(|receipt| receipt.thread.clone())
+		.unwrap_or_default()
+}
@@ -14,7 +14,7 @@
 };
 
 use self::data::{Data, ReceiptItem};
-use crate::{rooms, sending, Dep};
+use crate::{globals, rooms, sending, Dep};
 
 pub struct Service {
 	services: Services,
@@ -22,6 +22,7 @@ pub struct Service {
 }
 
 struct Services {
+	globals: Dep<globals::Service>,
 	sending: Dep<sending::Service>,
 	short: Dep<rooms::short::Service>,
 	timeline: Dep<rooms::timeline::Service>,
@@ -31,6 +32,7 @@ impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			services: Services {
+				globals: args.depend::<globals::Service>("globals"),
 				sending: args.depend::<sending::Service>("sending"),
 				short: args.depend::<rooms::short::Service>("rooms::short"),
 				timeline: args.depend::<rooms::timeline::Service>("rooms::timeline"),
@@ -51,11 +53,16 @@ pub async fn readreceipt_update(
 		event: &ReceiptEvent,
 	) {
 		self.db.readreceipt_update(user_id, room_id, event).await;
-		self.services
-			.sending
-			.flush_room(room_id)
-			.await
-			.expect("room flush failed");
+
+		// Receipts from remote users came in over federation and are not
+		// forwarded; only wake the sender for our own users' receipts.
+		if self.services.globals.user_is_local(user_id) {
+			self.services
+				.sending
+				.flush_room(room_id)
+				.await
+				.expect("room flush failed");
+		}
 	}
 
 	/// Gets the latest private read receipt from the user in the room
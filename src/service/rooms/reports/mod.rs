@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use conduwuit::{implement, utils::stream::TryIgnore, Result};
+use database::{Deserialized, Json, Map};
+use futures::Stream;
+use ruma::{Int, MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedRoomId, OwnedUserId};
+use serde::{Deserialize, Serialize};
+
+use crate::{globals, Dep};
+
+pub struct Service {
+	db: Data,
+	services: Services,
+}
+
+struct Data {
+	reportid_report: Arc<Map>,
+}
+
+struct Services {
+	globals: Dep<globals::Service>,
+}
+
+/// A room or event report made by a local user.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Report {
+	pub reporter: OwnedUserId,
+	pub room_id: OwnedRoomId,
+	pub event_id: Option<OwnedEventId>,
+	pub reason: Option<String>,
+	pub score: Option<Int>,
+	pub received_ts: MilliSecondsSinceUnixEpoch,
+}
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			db: Data {
+				reportid_report: args.db["reportid_report"].clone(),
+			},
+			services: Services {
+				globals: args.depend::<globals::Service>("globals"),
+			},
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Stores a report, returning its ID.
+#[implement(Service)]
+pub fn add_report(&self, report: &Report) -> Result<u64> {
+	let report_id = self.services.globals.next_count()?;
+	self.db.reportid_report.put(report_id, Json(report));
+
+	Ok(report_id)
+}
+
+#[implement(Service)]
+pub async fn get_report(&self, report_id: u64) -> Result<Report> {
+	self.db
+		.reportid_report
+		.qry(&report_id)
+		.await
+		.deserialized()
+}
+
+#[implement(Service)]
+pub fn remove_report(&self, report_id: u64) { self.db.reportid_report.del(report_id); }
+
+/// Returns all stored reports, newest first.
+#[implement(Service)]
+pub fn reports(&self) -> impl Stream<Item = (u64, Report)> + Send + '_ {
+	self.db.reportid_report.rev_stream().ignore_err()
+}
@@ -1,38 +1,74 @@
-use std::sync::Arc;
+use std::{
+	ops::Bound,
+	path::PathBuf,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, RwLock,
+	},
+	time::Duration,
+};
 
-use arrayvec::ArrayVec;
+use async_trait::async_trait;
 use conduwuit::{
-	implement,
+	debug, err, implement, info,
 	utils::{
-		set,
-		stream::{TryIgnore, WidebandExt},
-		ArrayVecExt, IterStream, ReadyExt,
+		stream::{BroadbandExt, TryIgnore},
+		IterStream, ReadyExt,
 	},
-	PduCount, PduEvent, Result,
+	warn, Err, Error, PduEvent, Result, Server,
+};
+use futures::{FutureExt, StreamExt};
+use ruma::{
+	api::client::search::search_events::v3::{Criteria, OrderBy},
+	events::TimelineEventType,
+	OwnedRoomId, RoomId, UserId,
+};
+use tantivy::{
+	collector::{Count, TopDocs},
+	directory::MmapDirectory,
+	query::{BooleanQuery, Query, QueryClone, QueryParser, RangeQuery, TermSetQuery},
+	schema::{Field, Schema, Value, FAST, INDEXED, STORED, TEXT},
+	DocAddress, Index, IndexReader, IndexWriter, Order, ReloadPolicy, Searcher, TantivyDocument,
+	TantivyError, Term,
+};
+use tokio::{
+	sync::Notify,
+	time::{interval, MissedTickBehavior},
 };
-use database::{keyval::Val, Map};
-use futures::{Stream, StreamExt};
-use ruma::{api::client::search::search_events::v3::Criteria, RoomId, UserId};
 
 use crate::{
 	rooms,
 	rooms::{
 		short::ShortRoomId,
-		timeline::{PduId, RawPduId},
+		timeline::{ExtractBody, PduId, RawPduId},
 	},
 	Dep,
 };
 
+/// Full-text index of message bodies, kept in its own directory next to the
+/// database. Writes are buffered by the index writer and become searchable
+/// once the worker commits them.
 pub struct Service {
-	db: Data,
+	index: Index,
+	reader: IndexReader,
+	writer: RwLock<IndexWriter>,
+	fields: Fields,
+	created: bool,
+	dirty: AtomicBool,
+	interrupt: Notify,
 	services: Services,
 }
 
-struct Data {
-	tokenids: Arc<Map>,
+struct Fields {
+	room: Field,
+	pdu_id: Field,
+	count: Field,
+	body: Field,
 }
 
 struct Services {
+	server: Arc<Server>,
+	metadata: Dep<rooms::metadata::Service>,
 	short: Dep<rooms::short::Service>,
 	state_accessor: Dep<rooms::state_accessor::Service>,
 	timeline: Dep<rooms::timeline::Service>,
@@ -40,24 +76,77 @@ struct Services {
 
 #[derive(Clone, Debug)]
 pub struct RoomQuery<'a> {
-	pub room_id: &'a RoomId,
-	pub user_id: Option<&'a UserId>,
+	pub rooms: &'a [OwnedRoomId],
+	pub user_id: &'a UserId,
 	pub criteria: &'a Criteria,
 	pub limit: usize,
-	pub skip: usize,
+	pub next_batch: Option<&'a str>,
+}
+
+#[derive(Debug, Default)]
+pub struct SearchResults {
+	/// Approximate number of matching messages, before visibility checks.
+	pub count: usize,
+	pub pdus: Vec<PduEvent>,
+	pub highlights: Vec<String>,
+	pub next_batch: Option<String>,
+}
+
+/// Where a search continues from. Recent-first searches resume below the
+/// `PduCount` of the last result, which is global and so serves as the cursor
+/// for every room at once; ranked searches skip the results already seen.
+#[derive(Clone, Copy, Debug)]
+enum Cursor {
+	Before(i64),
+	Skip(usize),
 }
 
-type TokenId = ArrayVec<u8, TOKEN_ID_MAX_LEN>;
+const COUNT_FIELD: &str = "count";
+const WRITER_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+const COMMIT_INTERVAL: Duration = Duration::from_secs(1);
 
-const TOKEN_ID_MAX_LEN: usize =
-	size_of::<ShortRoomId>() + WORD_MAX_LEN + 1 + size_of::<RawPduId>();
-const WORD_MAX_LEN: usize = 50;
+/// Upper bound on index pages read for one response, in case most hits are
+/// invisible to the user or rejected by the filter.
+const BATCH_MAX: usize = 20;
 
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		let mut schema = Schema::builder();
+		let fields = Fields {
+			room: schema.add_u64_field("room", INDEXED),
+			pdu_id: schema.add_bytes_field("pdu_id", INDEXED | STORED),
+			count: schema.add_i64_field(COUNT_FIELD, INDEXED | FAST),
+			body: schema.add_text_field("body", TEXT),
+		};
+
+		let path = index_dir(args.server);
+		let created = !path.exists();
+		std::fs::create_dir_all(&path)?;
+
+		let directory = MmapDirectory::open(&path).map_err(|e| index_error(e.into()))?;
+		let index = Index::open_or_create(directory, schema.build()).map_err(index_error)?;
+		let writer = index
+			.writer_with_num_threads(1, WRITER_MEMORY_BUDGET)
+			.map_err(index_error)?;
+
+		let reader = index
+			.reader_builder()
+			.reload_policy(ReloadPolicy::Manual)
+			.try_into()
+			.map_err(index_error)?;
+
 		Ok(Arc::new(Self {
-			db: Data { tokenids: args.db["tokenids"].clone() },
+			index,
+			reader,
+			writer: RwLock::new(writer),
+			fields,
+			created,
+			dirty: AtomicBool::new(false),
+			interrupt: Notify::new(),
 			services: Services {
+				server: args.server.clone(),
+				metadata: args.depend::<rooms::metadata::Service>("rooms::metadata"),
 				short: args.depend::<rooms::short::Service>("rooms::short"),
 				state_accessor: args
 					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
@@ -66,172 +155,289 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		}))
 	}
 
+	#[tracing::instrument(skip_all, name = "search", level = "debug")]
+	async fn worker(self: Arc<Self>) -> Result {
+		if self.created {
+			info!("Building message search index from stored timelines...");
+			match self.reindex_rooms().await {
+				| Ok(indexed) => info!("Indexed {indexed} messages for search."),
+				| Err(e) => warn!(
+					%e,
+					"Search index build did not finish; run the reindex-search admin command"
+				),
+			}
+		}
+
+		let mut i = interval(COMMIT_INTERVAL);
+		i.set_missed_tick_behavior(MissedTickBehavior::Delay);
+		loop {
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				_ = i.tick() => (),
+			}
+
+			if let Err(e) = self.commit().await {
+				warn!(%e, "Failed to commit search index");
+			}
+		}
+
+		self.commit().await
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_waiters(); }
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
 #[implement(Service)]
 pub fn index_pdu(&self, shortroomid: ShortRoomId, pdu_id: &RawPduId, message_body: &str) {
-	let batch = tokenize(message_body)
-		.map(|word| {
-			let mut key = shortroomid.to_be_bytes().to_vec();
-			key.extend_from_slice(word.as_bytes());
-			key.push(0xFF);
-			key.extend_from_slice(pdu_id.as_ref()); // TODO: currently we save the room id a second time here
-			key
-		})
-		.collect::<Vec<_>>();
+	let mut doc = TantivyDocument::new();
+	doc.add_u64(self.fields.room, shortroomid);
+	doc.add_bytes(self.fields.pdu_id, pdu_id.as_bytes());
+	doc.add_i64(self.fields.count, pdu_id.pdu_count().into_signed());
+	doc.add_text(self.fields.body, message_body);
 
-	self.db
-		.tokenids
-		.insert_batch(batch.iter().map(|k| (k.as_slice(), &[])));
+	if let Err(e) = self.writer.read().expect("locked").add_document(doc) {
+		warn!(?pdu_id, %e, "Failed to index message for search");
+		return;
+	}
+
+	self.dirty.store(true, Ordering::Release);
 }
 
 #[implement(Service)]
-pub fn deindex_pdu(&self, shortroomid: ShortRoomId, pdu_id: &RawPduId, message_body: &str) {
-	let batch = tokenize(message_body).map(|word| {
-		let mut key = shortroomid.to_be_bytes().to_vec();
-		key.extend_from_slice(word.as_bytes());
-		key.push(0xFF);
-		key.extend_from_slice(pdu_id.as_ref()); // TODO: currently we save the room id a second time here
-		key
-	});
+pub fn deindex_pdu(&self, pdu_id: &RawPduId) {
+	let term = Term::from_field_bytes(self.fields.pdu_id, pdu_id.as_bytes());
+	self.writer.read().expect("locked").delete_term(term);
+	self.dirty.store(true, Ordering::Release);
+}
 
-	for token in batch {
-		self.db.tokenids.remove(&token);
+/// Rebuilds the search index of every room known to the server. Returns the
+/// number of messages indexed.
+#[implement(Service)]
+pub async fn reindex_rooms(&self) -> Result<usize> {
+	let room_ids: Vec<OwnedRoomId> = self
+		.services
+		.metadata
+		.iter_ids()
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let mut indexed: usize = 0;
+	for room_id in &room_ids {
+		if !self.services.server.running() {
+			return Err!("Shutting down; search index is incomplete.");
+		}
+
+		match self.reindex_room(room_id).await {
+			| Ok(count) => indexed = indexed.saturating_add(count),
+			| Err(e) => debug!(?room_id, %e, "Skipping room while reindexing search"),
+		}
 	}
+
+	Ok(indexed)
 }
 
+/// Rebuilds the search index of a room from the locally stored timeline.
+/// Returns the number of messages indexed.
 #[implement(Service)]
-pub async fn search_pdus<'a>(
-	&'a self,
-	query: &'a RoomQuery<'a>,
-) -> Result<(usize, impl Stream<Item = PduEvent> + Send + 'a)> {
-	let pdu_ids: Vec<_> = self.search_pdu_ids(query).await?.collect().await;
-
-	let count = pdu_ids.len();
-	let pdus = pdu_ids
-		.into_iter()
-		.stream()
-		.wide_filter_map(move |result_pdu_id: RawPduId| async move {
-			self.services
-				.timeline
-				.get_pdu_from_id(&result_pdu_id)
-				.await
-				.ok()
+pub async fn reindex_room(&self, room_id: &RoomId) -> Result<usize> {
+	let shortroomid = self.services.short.get_shortroomid(room_id).await?;
+
+	let term = Term::from_field_u64(self.fields.room, shortroomid);
+	self.writer.read().expect("locked").delete_term(term);
+	self.dirty.store(true, Ordering::Release);
+
+	let indexed = self
+		.services
+		.timeline
+		.pdus(None, room_id, None)
+		.ignore_err()
+		.ready_filter(|(_, pdu)| pdu.kind == TimelineEventType::RoomMessage)
+		.ready_filter_map(|(count, pdu)| {
+			let content: ExtractBody = pdu.get_content().ok()?;
+			Some((count, content.body?))
 		})
-		.ready_filter(|pdu| !pdu.is_redacted())
-		.ready_filter(|pdu| pdu.matches(&query.criteria.filter))
-		.wide_filter_map(move |pdu| async move {
-			self.services
-				.state_accessor
-				.user_can_see_event(query.user_id?, &pdu.room_id, &pdu.event_id)
-				.await
-				.then_some(pdu)
+		.ready_fold(0_usize, |indexed, (shorteventid, body)| {
+			let pdu_id: RawPduId = PduId { shortroomid, shorteventid }.into();
+			self.index_pdu(shortroomid, &pdu_id, &body);
+			indexed.saturating_add(1)
 		})
-		.skip(query.skip)
-		.take(query.limit);
+		.await;
 
-	Ok((count, pdus))
+	Ok(indexed)
 }
 
-// result is modeled as a stream such that callers don't have to be refactored
-// though an additional async/wrap still exists for now
+/// Searches the given rooms for messages the user can see, most recent or
+/// best ranked first depending on the criteria.
 #[implement(Service)]
-pub async fn search_pdu_ids(
-	&self,
-	query: &RoomQuery<'_>,
-) -> Result<impl Stream<Item = RawPduId> + Send + '_> {
-	let shortroomid = self.services.short.get_shortroomid(query.room_id).await?;
+pub async fn search_pdus(&self, query: &RoomQuery<'_>) -> Result<SearchResults> {
+	let by_rank = matches!(query.criteria.order_by, Some(OrderBy::Rank));
+	let mut cursor = match query.next_batch {
+		| None if by_rank => Cursor::Skip(0),
+		| None => Cursor::Before(i64::MAX),
+		| Some(next_batch) if by_rank => Cursor::Skip(next_batch.parse()?),
+		| Some(next_batch) => Cursor::Before(next_batch.parse()?),
+	};
+
+	let rooms: Vec<Term> = query
+		.rooms
+		.iter()
+		.stream()
+		.broad_filter_map(|room_id| self.services.short.get_shortroomid(room_id).map(Result::ok))
+		.map(|shortroomid| Term::from_field_u64(self.fields.room, shortroomid))
+		.collect()
+		.await;
+
+	let mut parser = QueryParser::for_index(&self.index, vec![self.fields.body]);
+	parser.set_conjunction_by_default();
+	let (terms, _) = parser.parse_query_lenient(&query.criteria.search_term);
+
+	let mut highlights = Vec::new();
+	terms.query_terms(&mut |term, _| {
+		if let Some(word) = term.value().as_str() {
+			highlights.push(word.to_owned());
+		}
+	});
+
+	let matching = BooleanQuery::intersection(vec![terms, Box::new(TermSetQuery::new(rooms))]);
 
-	let pdu_ids = self.search_pdu_ids_query_room(query, shortroomid).await;
+	let searcher = self.reader.searcher();
+	let count = searcher.search(&matching, &Count).map_err(index_error)?;
 
-	let iters = pdu_ids.into_iter().map(IntoIterator::into_iter);
+	let mut pdus = Vec::with_capacity(query.limit);
+	let mut next_batch = None;
+	for _ in 0..BATCH_MAX {
+		let hits = self.search_page(&searcher, &matching, cursor, query.limit)?;
+		let exhausted = hits.len() < query.limit;
+		for (position, pdu_id) in hits {
+			cursor = position;
+			if let Some(pdu) = self.visible_pdu(query, &pdu_id).await {
+				pdus.push(pdu);
+			}
 
-	Ok(set::intersection(iters).stream())
+			if pdus.len() >= query.limit {
+				break;
+			}
+		}
+
+		if pdus.len() >= query.limit || !exhausted {
+			next_batch = Some(cursor);
+		}
+
+		if pdus.len() >= query.limit || exhausted {
+			break;
+		}
+	}
+
+	Ok(SearchResults {
+		count,
+		pdus,
+		highlights,
+		next_batch: next_batch.map(|cursor| match cursor {
+			| Cursor::Before(count) => count.to_string(),
+			| Cursor::Skip(skip) => skip.to_string(),
+		}),
+	})
 }
 
+/// Reads up to `limit` hits following the cursor, each with the cursor
+/// positioned just past it.
 #[implement(Service)]
-async fn search_pdu_ids_query_room(
+fn search_page(
 	&self,
-	query: &RoomQuery<'_>,
-	shortroomid: ShortRoomId,
-) -> Vec<Vec<RawPduId>> {
-	tokenize(&query.criteria.search_term)
-		.stream()
-		.wide_then(|word| async move {
-			self.search_pdu_ids_query_words(shortroomid, &word)
-				.collect::<Vec<_>>()
-				.await
-		})
-		.collect::<Vec<_>>()
-		.await
+	searcher: &Searcher,
+	matching: &BooleanQuery,
+	cursor: Cursor,
+	limit: usize,
+) -> Result<Vec<(Cursor, RawPduId)>> {
+	match cursor {
+		| Cursor::Before(count) => {
+			let before = Term::from_field_i64(self.fields.count, count);
+			let before = RangeQuery::new(Bound::Unbounded, Bound::Excluded(before));
+			let query = BooleanQuery::intersection(vec![matching.box_clone(), Box::new(before)]);
+			let collector =
+				TopDocs::with_limit(limit).order_by_fast_field::<i64>(COUNT_FIELD, Order::Desc);
+
+			searcher
+				.search(&query, &collector)
+				.map_err(index_error)?
+				.into_iter()
+				.map(|(count, addr)| {
+					Ok((Cursor::Before(count), self.get_pdu_id(searcher, addr)?))
+				})
+				.collect()
+		},
+		| Cursor::Skip(skip) => {
+			let collector = TopDocs::with_limit(limit).and_offset(skip);
+
+			searcher
+				.search(matching, &collector)
+				.map_err(index_error)?
+				.into_iter()
+				.enumerate()
+				.map(|(i, (_, addr))| {
+					let skip = skip.saturating_add(i).saturating_add(1);
+					Ok((Cursor::Skip(skip), self.get_pdu_id(searcher, addr)?))
+				})
+				.collect()
+		},
+	}
 }
 
-/// Iterate over PduId's containing a word
 #[implement(Service)]
-fn search_pdu_ids_query_words<'a>(
-	&'a self,
-	shortroomid: ShortRoomId,
-	word: &'a str,
-) -> impl Stream<Item = RawPduId> + Send + '_ {
-	self.search_pdu_ids_query_word(shortroomid, word)
-		.map(move |key| -> RawPduId {
-			let key = &key[prefix_len(word)..];
-			key.into()
-		})
+fn get_pdu_id(&self, searcher: &Searcher, addr: DocAddress) -> Result<RawPduId> {
+	let doc: TantivyDocument = searcher.doc(addr).map_err(index_error)?;
+
+	doc.get_first(self.fields.pdu_id)
+		.and_then(|value| value.as_bytes())
+		.map(Into::into)
+		.ok_or_else(|| err!(Database("Search index document is missing its pdu_id")))
 }
 
-/// Iterate over raw database results for a word
 #[implement(Service)]
-fn search_pdu_ids_query_word(
-	&self,
-	shortroomid: ShortRoomId,
-	word: &str,
-) -> impl Stream<Item = Val<'_>> + Send + '_ {
-	// rustc says const'ing this not yet stable
-	let end_id: RawPduId = PduId {
-		shortroomid,
-		shorteventid: PduCount::max(),
+async fn visible_pdu(&self, query: &RoomQuery<'_>, pdu_id: &RawPduId) -> Option<PduEvent> {
+	let pdu = self.services.timeline.get_pdu_from_id(pdu_id).await.ok()?;
+
+	if pdu.is_redacted() || !pdu.matches(&query.criteria.filter) {
+		return None;
 	}
-	.into();
-
-	// Newest pdus first
-	let end = make_tokenid(shortroomid, word, &end_id);
-	let prefix = make_prefix(shortroomid, word);
-	self.db
-		.tokenids
-		.rev_raw_keys_from(&end)
-		.ignore_err()
-		.ready_take_while(move |key| key.starts_with(&prefix))
-}
 
-/// Splits a string into tokens used as keys in the search inverted index
-///
-/// This may be used to tokenize both message bodies (for indexing) or search
-/// queries (for querying).
-fn tokenize(body: &str) -> impl Iterator<Item = String> + Send + '_ {
-	body.split_terminator(|c: char| !c.is_alphanumeric())
-		.filter(|s| !s.is_empty())
-		.filter(|word| word.len() <= WORD_MAX_LEN)
-		.map(str::to_lowercase)
+	self.services
+		.state_accessor
+		.user_can_see_event(query.user_id, &pdu.room_id, &pdu.event_id)
+		.await
+		.then_some(pdu)
 }
 
-fn make_tokenid(shortroomid: ShortRoomId, word: &str, pdu_id: &RawPduId) -> TokenId {
-	let mut key = make_prefix(shortroomid, word);
-	key.extend_from_slice(pdu_id.as_ref());
-	key
-}
+/// Makes buffered writes durable and visible to searches.
+#[implement(Service)]
+async fn commit(self: &Arc<Self>) -> Result {
+	if !self.dirty.swap(false, Ordering::AcqRel) {
+		return Ok(());
+	}
 
-fn make_prefix(shortroomid: ShortRoomId, word: &str) -> TokenId {
-	let mut key = TokenId::new();
-	key.extend_from_slice(&shortroomid.to_be_bytes());
-	key.extend_from_slice(word.as_bytes());
-	key.push(database::SEP);
-	key
+	let this = self.clone();
+	self.services
+		.server
+		.runtime()
+		.spawn_blocking(move || {
+			this.writer
+				.write()
+				.expect("locked")
+				.commit()
+				.and_then(|_| this.reader.reload())
+		})
+		.await?
+		.map_err(index_error)
 }
 
-fn prefix_len(word: &str) -> usize {
-	size_of::<ShortRoomId>()
-		.saturating_add(word.len())
-		.saturating_add(1)
+fn index_dir(server: &Server) -> PathBuf {
+	let mut r = PathBuf::new();
+	r.push(server.config.database_path.clone());
+	r.push("search");
+	r
 }
+
+fn index_error(e: TantivyError) -> Error { err!(Database("Search index error: {e}")) }
@@ -41,11 +41,21 @@
 
 pub struct Service {
 	pub server_visibility_cache: Mutex<LruCache<(OwnedServerName, ShortStateHash), bool>>,
-	pub user_visibility_cache: Mutex<LruCache<(OwnedUserId, ShortStateHash), bool>>,
+	pub user_visibility_cache: Mutex<LruCache<(OwnedUserId, ShortStateHash), UserVisibility>>,
 	services: Services,
 	db: Data,
 }
 
+/// Whether a user can see the events with some state, as cached.
+#[derive(Clone, Copy, Debug)]
+pub enum UserVisibility {
+	/// Decided by the state alone.
+	Fixed(bool),
+	/// Shared history the user wasn't joined for, visible while the user is
+	/// currently joined to the room.
+	WhileJoined,
+}
+
 struct Services {
 	short: Dep<rooms::short::Service>,
 	state: Dep<rooms::state::Service>,
@@ -163,6 +173,14 @@ pub async fn is_world_readable(&self, room_id: &RoomId) -> bool {
 			.unwrap_or(false)
 	}
 
+	/// Checks if the room may be shared with other servers, i.e. it was not
+	/// created with `m.federate` set to false
+	pub async fn is_federated(&self, room_id: &RoomId) -> bool {
+		self.room_state_get_content(room_id, &StateEventType::RoomCreate, "")
+			.await
+			.map_or(true, |c: RoomCreateEventContent| c.federate)
+	}
+
 	/// Checks if guests are able to join a given room
 	pub async fn guest_can_join(&self, room_id: &RoomId) -> bool {
 		self.room_state_get_content(room_id, &StateEventType::RoomGuestAccess, "")
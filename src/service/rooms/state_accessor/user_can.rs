@@ -11,7 +11,8 @@
 	EventId, RoomId, UserId,
 };
 
-use crate::rooms::state::RoomMutexGuard;
+use super::UserVisibility;
+use crate::rooms::{short::ShortStateHash, state::RoomMutexGuard};
 
 /// Checks if a given user can redact a given event
 ///
@@ -96,17 +97,41 @@ pub async fn user_can_see_event(
 		return true;
 	};
 
-	if let Some(visibility) = self
+	let cached = self
 		.user_visibility_cache
 		.lock()
 		.expect("locked")
 		.get_mut(&(user_id.to_owned(), shortstatehash))
-	{
-		return *visibility;
-	}
+		.copied();
+
+	let visibility = match cached {
+		| Some(visibility) => visibility,
+		| None => {
+			let visibility = self.user_visibility(shortstatehash, user_id).await;
+			self.user_visibility_cache
+				.lock()
+				.expect("locked")
+				.insert((user_id.to_owned(), shortstatehash), visibility);
+
+			visibility
+		},
+	};
 
-	let currently_member = self.services.state_cache.is_joined(user_id, room_id).await;
+	match visibility {
+		| UserVisibility::Fixed(visible) => visible,
+		| UserVisibility::WhileJoined =>
+			self.services.state_cache.is_joined(user_id, room_id).await,
+	}
+}
 
+/// A user's visibility of the events with the state, based on the room's
+/// history_visibility in it.
+#[implement(super::Service)]
+async fn user_visibility(
+	&self,
+	shortstatehash: ShortStateHash,
+	user_id: &UserId,
+) -> UserVisibility {
 	let history_visibility = self
 		.state_get_content(shortstatehash, &StateEventType::RoomHistoryVisibility, "")
 		.await
@@ -114,29 +139,29 @@ pub async fn user_can_see_event(
 			c.history_visibility
 		});
 
-	let visibility = match history_visibility {
-		| HistoryVisibility::WorldReadable => true,
-		| HistoryVisibility::Shared => currently_member,
+	match history_visibility {
+		| HistoryVisibility::WorldReadable => UserVisibility::Fixed(true),
+		| HistoryVisibility::Shared => {
+			// Allow if the user was joined at the event, or is currently joined
+			if self.user_was_joined(shortstatehash, user_id).await {
+				UserVisibility::Fixed(true)
+			} else {
+				UserVisibility::WhileJoined
+			}
+		},
 		| HistoryVisibility::Invited => {
 			// Allow if any member on requesting server was AT LEAST invited, else deny
-			self.user_was_invited(shortstatehash, user_id).await
+			UserVisibility::Fixed(self.user_was_invited(shortstatehash, user_id).await)
 		},
 		| HistoryVisibility::Joined => {
 			// Allow if any member on requested server was joined, else deny
-			self.user_was_joined(shortstatehash, user_id).await
+			UserVisibility::Fixed(self.user_was_joined(shortstatehash, user_id).await)
 		},
 		| _ => {
 			error!("Unknown history visibility {history_visibility}");
-			false
+			UserVisibility::Fixed(false)
 		},
-	};
-
-	self.user_visibility_cache
-		.lock()
-		.expect("locked")
-		.insert((user_id.to_owned(), shortstatehash), visibility);
-
-	visibility
+	}
 }
 
 /// Whether a user is allowed to see an event, based on
@@ -20,6 +20,8 @@ pub(super) struct Data {
 	pduid_pdu: Arc<Map>,
 	userroomid_highlightcount: Arc<Map>,
 	userroomid_notificationcount: Arc<Map>,
+	userroomthreadid_highlightcount: Arc<Map>,
+	userroomthreadid_notificationcount: Arc<Map>,
 	pub(super) db: Arc<Database>,
 	services: Services,
 }
@@ -39,6 +41,8 @@ pub(super) fn new(args: &crate::Args<'_>) -> Self {
 			pduid_pdu: db["pduid_pdu"].clone(),
 			userroomid_highlightcount: db["userroomid_highlightcount"].clone(),
 			userroomid_notificationcount: db["userroomid_notificationcount"].clone(),
+			userroomthreadid_highlightcount: db["userroomthreadid_highlightcount"].clone(),
+			userroomthreadid_notificationcount: db["userroomthreadid_notificationcount"].clone(),
 			db: args.db.clone(),
 			services: Services {
 				short: args.depend::<rooms::short::Service>("rooms::short"),
@@ -270,26 +274,43 @@ fn each_pdu((pdu_id, pdu): KeyVal<'_>, user_id: Option<&UserId>) -> Result<PdusI
 		Ok((pdu_id.pdu_count(), pdu))
 	}
 
+	/// Counts a notification of the users in the room, or in the thread of the
+	/// room if `thread` is its root.
 	pub(super) fn increment_notification_counts(
 		&self,
 		room_id: &RoomId,
+		thread: Option<&EventId>,
 		notifies: Vec<OwnedUserId>,
 		highlights: Vec<OwnedUserId>,
 	) {
 		let _cork = self.db.cork();
 
+		let (notificationcount, highlightcount) = match thread {
+			| Some(_) => (
+				&self.userroomthreadid_notificationcount,
+				&self.userroomthreadid_highlightcount,
+			),
+			| None => (&self.userroomid_notificationcount, &self.userroomid_highlightcount),
+		};
+
+		let key = |user: &UserId| {
+			let mut key = user.as_bytes().to_vec();
+			key.push(0xFF);
+			key.extend_from_slice(room_id.as_bytes());
+			if let Some(thread) = thread {
+				key.push(0xFF);
+				key.extend_from_slice(thread.as_bytes());
+			}
+
+			key
+		};
+
 		for user in notifies {
-			let mut userroom_id = user.as_bytes().to_vec();
-			userroom_id.push(0xFF);
-			userroom_id.extend_from_slice(room_id.as_bytes());
-			increment(&self.userroomid_notificationcount, &userroom_id);
+			increment(notificationcount, &key(&user));
 		}
 
 		for user in highlights {
-			let mut userroom_id = user.as_bytes().to_vec();
-			userroom_id.push(0xFF);
-			userroom_id.extend_from_slice(room_id.as_bytes());
-			increment(&self.userroomid_highlightcount, &userroom_id);
+			increment(highlightcount, &key(&user));
 		}
 	}
 
@@ -11,7 +11,7 @@
 
 use conduwuit::{
 	at, debug, debug_warn, err, error, implement, info,
-	pdu::{gen_event_id, EventHash, PduBuilder, PduCount, PduEvent},
+	pdu::{check_pdu_limits, gen_event_id, EventHash, PduBuilder, PduCount, PduEvent},
 	utils::{
 		self, future::TryExtExt, stream::TryIgnore, IterStream, MutexMap, MutexMapGuard, ReadyExt,
 	},
@@ -46,11 +46,8 @@
 use self::data::Data;
 pub use self::data::PdusIterItem;
 use crate::{
-	account_data, admin, appservice,
-	appservice::NamespaceRegex,
-	globals, pusher, rooms,
-	rooms::{short::ShortRoomId, state_compressor::CompressedState},
-	sending, server_keys, users, Dep,
+	account_data, admin, appservice, appservice::NamespaceRegex, globals, pusher, rooms,
+	rooms::state_compressor::CompressedState, sending, server_keys, users, Dep,
 };
 
 // Update Relationships
@@ -71,8 +68,8 @@ struct ExtractRelatesToEventId {
 }
 
 #[derive(Deserialize)]
-struct ExtractBody {
-	body: Option<String>,
+pub(crate) struct ExtractBody {
+	pub(crate) body: Option<String>,
 }
 
 pub struct Service {
@@ -343,15 +340,31 @@ pub async fn append_pdu<'a, Leafs>(
 
 		let insert_lock = self.mutex_insert.lock(&pdu.room_id).await;
 
+		let thread = pdu
+			.get_content::<ExtractRelatesTo>()
+			.ok()
+			.and_then(|content| match content.relates_to {
+				| Relation::Thread(thread) => Some(thread.event_id),
+				| _ => None,
+			});
+
 		let count1 = self.services.globals.next_count()?;
 		// Mark as read first so the sending client doesn't get a notification even if
 		// appending fails
 		self.services
 			.read_receipt
 			.private_read_set(&pdu.room_id, &pdu.sender, count1);
-		self.services
-			.user
-			.reset_notification_counts(&pdu.sender, &pdu.room_id);
+		match &thread {
+			| Some(thread) => self.services.user.reset_thread_notification_counts(
+				&pdu.sender,
+				&pdu.room_id,
+				thread,
+			),
+			| None => self
+				.services
+				.user
+				.reset_main_notification_counts(&pdu.sender, &pdu.room_id),
+		}
 
 		let count2 = PduCount::Normal(self.services.globals.next_count().unwrap());
 		let pdu_id: RawPduId = PduId { shortroomid, shorteventid: count2 }.into();
@@ -396,6 +409,11 @@ pub async fn append_pdu<'a, Leafs>(
 		}
 
 		for user in &push_target {
+			// Don't notify users of events from users they have ignored
+			if self.services.users.user_is_ignored(&pdu.sender, user).await {
+				continue;
+			}
+
 			let rules_for_user = self
 				.services
 				.account_data
@@ -449,8 +467,12 @@ pub async fn append_pdu<'a, Leafs>(
 				.await;
 		}
 
-		self.db
-			.increment_notification_counts(&pdu.room_id, notifies, highlights);
+		self.db.increment_notification_counts(
+			&pdu.room_id,
+			thread.as_deref(),
+			notifies,
+			highlights,
+		);
 
 		match pdu.kind {
 			| TimelineEventType::RoomRedaction => {
@@ -466,7 +488,7 @@ pub async fn append_pdu<'a, Leafs>(
 								.user_can_redact(redact_id, &pdu.sender, &pdu.room_id, false)
 								.await?
 							{
-								self.redact_pdu(redact_id, pdu, shortroomid).await?;
+								self.redact_pdu(redact_id, pdu).await?;
 							}
 						}
 					},
@@ -479,7 +501,7 @@ pub async fn append_pdu<'a, Leafs>(
 								.user_can_redact(redact_id, &pdu.sender, &pdu.room_id, false)
 								.await?
 							{
-								self.redact_pdu(redact_id, pdu, shortroomid).await?;
+								self.redact_pdu(redact_id, pdu).await?;
 							}
 						}
 					},
@@ -751,14 +773,14 @@ pub async fn create_hash_and_sign_event(
 			ready(auth_events.get(&key))
 		};
 
-		let auth_check = state_res::auth_check(
-			&room_version,
-			&pdu,
-			None, // TODO: third_party_invite
-			auth_fetch,
-		)
-		.await
-		.map_err(|e| err!(Request(Forbidden(warn!("Auth check failed: {e:?}")))))?;
+		let third_party_invite = pdu
+			.third_party_invite_token()
+			.and_then(|token| auth_events.get(&(StateEventType::RoomThirdPartyInvite, token)));
+
+		let auth_check =
+			state_res::auth_check(&room_version, &pdu, third_party_invite, auth_fetch)
+				.await
+				.map_err(|e| err!(Request(Forbidden(warn!("Auth check failed: {e:?}")))))?;
 
 		if !auth_check {
 			return Err!(Request(Forbidden("Event is not authorized.")));
@@ -784,6 +806,8 @@ pub async fn create_hash_and_sign_event(
 				.expect("server name is a valid CanonicalJsonValue"),
 		);
 
+		check_pdu_limits(&pdu_json)?;
+
 		if let Err(e) = self
 			.services
 			.server_keys
@@ -1022,12 +1046,7 @@ pub fn pdus<'a>(
 
 	/// Replace a PDU with the redacted form.
 	#[tracing::instrument(name = "redact", level = "debug", skip(self))]
-	pub async fn redact_pdu(
-		&self,
-		event_id: &EventId,
-		reason: &PduEvent,
-		shortroomid: ShortRoomId,
-	) -> Result {
+	pub async fn redact_pdu(&self, event_id: &EventId, reason: &PduEvent) -> Result {
 		// TODO: Don't reserialize, keep original json
 		let Ok(pdu_id) = self.get_pdu_id(event_id).await else {
 			// If event does not exist, just noop
@@ -1038,13 +1057,7 @@ pub async fn redact_pdu(
 			err!(Database(error!(?pdu_id, ?event_id, ?e, "PDU ID points to invalid PDU.")))
 		})?;
 
-		if let Ok(content) = pdu.get_content::<ExtractBody>() {
-			if let Some(body) = content.body {
-				self.services
-					.search
-					.deindex_pdu(shortroomid, &pdu_id, &body);
-			}
-		}
+		self.services.search.deindex_pdu(&pdu_id);
 
 		let room_version_id = self.services.state.get_room_version(&pdu.room_id).await?;
 
@@ -1125,6 +1138,12 @@ pub async fn backfill_if_required(&self, room_id: &RoomId, from: PduCount) -> Re
 					.map(ToOwned::to_owned)
 					.stream(),
 			)
+			.chain(
+				self.services
+					.state_cache
+					.room_servers(room_id)
+					.map(ToOwned::to_owned),
+			)
 			.ready_filter(|server_name| !self.services.globals.server_is_ours(server_name))
 			.filter_map(|server_name| async move {
 				self.services
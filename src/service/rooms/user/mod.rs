@@ -1,8 +1,13 @@
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
-use conduwuit::{implement, Result};
-use database::{Database, Deserialized, Map};
-use ruma::{RoomId, UserId};
+use conduwuit::{
+	implement,
+	utils::{stream::TryIgnore, ReadyExt},
+	Result,
+};
+use database::{Database, Deserialized, Ignore, Interfix, Map};
+use futures::StreamExt;
+use ruma::{EventId, OwnedEventId, RoomId, UserId};
 
 use crate::{globals, rooms, rooms::short::ShortStateHash, Dep};
 
@@ -15,6 +20,8 @@ struct Data {
 	db: Arc<Database>,
 	userroomid_notificationcount: Arc<Map>,
 	userroomid_highlightcount: Arc<Map>,
+	userroomthreadid_notificationcount: Arc<Map>,
+	userroomthreadid_highlightcount: Arc<Map>,
 	roomuserid_lastnotificationread: Arc<Map>,
 	roomsynctoken_shortstatehash: Arc<Map>,
 }
@@ -31,6 +38,10 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				db: args.db.clone(),
 				userroomid_notificationcount: args.db["userroomid_notificationcount"].clone(),
 				userroomid_highlightcount: args.db["userroomid_highlightcount"].clone(),
+				userroomthreadid_notificationcount: args.db["userroomthreadid_notificationcount"]
+					.clone(),
+				userroomthreadid_highlightcount: args.db["userroomthreadid_highlightcount"]
+					.clone(),
 				roomuserid_lastnotificationread: args.db["userroomid_highlightcount"].clone(),
 				roomsynctoken_shortstatehash: args.db["roomsynctoken_shortstatehash"].clone(),
 			},
@@ -45,12 +56,55 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
+/// Resets the notification counts of the room and all of its threads, e.g.
+/// for an unthreaded read receipt.
 #[implement(Service)]
-pub fn reset_notification_counts(&self, user_id: &UserId, room_id: &RoomId) {
+pub async fn reset_notification_counts(&self, user_id: &UserId, room_id: &RoomId) {
+	let prefix = (user_id, room_id, Interfix);
+	for map in [
+		&self.db.userroomthreadid_notificationcount,
+		&self.db.userroomthreadid_highlightcount,
+	] {
+		map.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| map.remove(key))
+			.await;
+	}
+
+	self.reset_main_notification_counts(user_id, room_id);
+}
+
+/// Resets the notification counts of the room outside of threads.
+#[implement(Service)]
+pub fn reset_main_notification_counts(&self, user_id: &UserId, room_id: &RoomId) {
 	let userroom_id = (user_id, room_id);
 	self.db.userroomid_highlightcount.put(userroom_id, 0_u64);
 	self.db.userroomid_notificationcount.put(userroom_id, 0_u64);
 
+	self.notification_read(user_id, room_id);
+}
+
+/// Resets the notification counts of a thread of the room.
+#[implement(Service)]
+pub fn reset_thread_notification_counts(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	thread: &EventId,
+) {
+	let userroomthread_id = (user_id, room_id, thread);
+	self.db
+		.userroomthreadid_highlightcount
+		.del(userroomthread_id);
+	self.db
+		.userroomthreadid_notificationcount
+		.del(userroomthread_id);
+
+	self.notification_read(user_id, room_id);
+}
+
+#[implement(Service)]
+fn notification_read(&self, user_id: &UserId, room_id: &RoomId) {
 	let roomuser_id = (room_id, user_id);
 	let count = self.services.globals.next_count().unwrap();
 	self.db
@@ -58,26 +112,73 @@ pub fn reset_notification_counts(&self, user_id: &UserId, room_id: &RoomId) {
 		.put(roomuser_id, count);
 }
 
+/// Number of unread notifications in the room, including its threads.
 #[implement(Service)]
 pub async fn notification_count(&self, user_id: &UserId, room_id: &RoomId) -> u64 {
 	let key = (user_id, room_id);
-	self.db
+	let main: u64 = self
+		.db
 		.userroomid_notificationcount
 		.qry(&key)
 		.await
 		.deserialized()
-		.unwrap_or(0)
+		.unwrap_or(0);
+
+	self.thread_notification_counts(user_id, room_id)
+		.await
+		.values()
+		.fold(main, |count, (notifications, _)| count.saturating_add(*notifications))
 }
 
+/// Number of unread highlights in the room, including its threads.
 #[implement(Service)]
 pub async fn highlight_count(&self, user_id: &UserId, room_id: &RoomId) -> u64 {
 	let key = (user_id, room_id);
-	self.db
+	let main: u64 = self
+		.db
 		.userroomid_highlightcount
 		.qry(&key)
 		.await
 		.deserialized()
-		.unwrap_or(0)
+		.unwrap_or(0);
+
+	self.thread_notification_counts(user_id, room_id)
+		.await
+		.values()
+		.fold(main, |count, (_, highlights)| count.saturating_add(*highlights))
+}
+
+/// Numbers of unread notifications and highlights of the threads in the room
+/// which have any, by thread root.
+#[implement(Service)]
+pub async fn thread_notification_counts(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+) -> BTreeMap<OwnedEventId, (u64, u64)> {
+	type KeyVal<'a> = ((Ignore, Ignore, &'a EventId), u64);
+
+	let mut counts = BTreeMap::<OwnedEventId, (u64, u64)>::new();
+	let prefix = (user_id, room_id, Interfix);
+	self.db
+		.userroomthreadid_notificationcount
+		.stream_prefix(&prefix)
+		.ignore_err()
+		.ready_for_each(|((Ignore, Ignore, thread), count): KeyVal<'_>| {
+			counts.entry(thread.to_owned()).or_default().0 = count;
+		})
+		.await;
+
+	self.db
+		.userroomthreadid_highlightcount
+		.stream_prefix(&prefix)
+		.ignore_err()
+		.ready_for_each(|((Ignore, Ignore, thread), count): KeyVal<'_>| {
+			counts.entry(thread.to_owned()).or_default().1 = count;
+		})
+		.await;
+
+	counts
 }
 
 #[implement(Service)]
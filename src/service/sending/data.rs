@@ -153,6 +153,19 @@ pub(super) fn queue_requests<'a, I>(&self, requests: I) -> Vec<Vec<u8>>
 		keys
 	}
 
+	#[inline]
+	pub fn all_queued_requests(&self) -> impl Stream<Item = OutgoingItem> + Send + '_ {
+		self.servernameevent_data
+			.raw_stream()
+			.ignore_err()
+			.map(|(key, val)| {
+				let (dest, event) =
+					parse_servercurrentevent(key, val).expect("invalid servercurrentevent");
+
+				(key.to_vec(), event, dest)
+			})
+	}
+
 	pub fn queued_requests(
 		&self,
 		destination: &Destination,
@@ -19,6 +19,7 @@
 use futures::{FutureExt, Stream, StreamExt};
 use ruma::{
 	api::{appservice::Registration, OutgoingRequest},
+	events::{room::server_acl::RoomServerAclEventContent, StateEventType},
 	RoomId, ServerName, UserId,
 };
 use smallvec::SmallVec;
@@ -46,6 +47,7 @@ struct Services {
 	globals: Dep<globals::Service>,
 	state: Dep<rooms::state::Service>,
 	state_cache: Dep<rooms::state_cache::Service>,
+	state_accessor: Dep<rooms::state_accessor::Service>,
 	user: Dep<rooms::user::Service>,
 	users: Dep<users::Service>,
 	presence: Dep<presence::Service>,
@@ -90,6 +92,8 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				globals: args.depend::<globals::Service>("globals"),
 				state: args.depend::<rooms::state::Service>("rooms::state"),
 				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
+				state_accessor: args
+					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
 				user: args.depend::<rooms::user::Service>("rooms::user"),
 				users: args.depend::<users::Service>("users"),
 				presence: args.depend::<presence::Service>("presence"),
@@ -170,11 +174,7 @@ pub fn send_pdu_appservice(&self, appservice_id: String, pdu_id: RawPduId) -> Re
 
 	#[tracing::instrument(skip(self, room_id, pdu_id), level = "debug")]
 	pub async fn send_pdu_room(&self, room_id: &RoomId, pdu_id: &RawPduId) -> Result {
-		let servers = self
-			.services
-			.state_cache
-			.room_servers(room_id)
-			.ready_filter(|server_name| !self.services.globals.server_is_ours(server_name));
+		let servers = self.room_destinations(room_id).await;
 
 		self.send_pdu_servers(servers, pdu_id).await
 	}
@@ -216,11 +216,7 @@ pub fn send_edu_server(&self, server: &ServerName, serialized: EduBuf) -> Result
 
 	#[tracing::instrument(skip(self, room_id, serialized), level = "debug")]
 	pub async fn send_edu_room(&self, room_id: &RoomId, serialized: EduBuf) -> Result {
-		let servers = self
-			.services
-			.state_cache
-			.room_servers(room_id)
-			.ready_filter(|server_name| !self.services.globals.server_is_ours(server_name));
+		let servers = self.room_destinations(room_id).await;
 
 		self.send_edu_servers(servers, serialized).await
 	}
@@ -252,13 +248,35 @@ pub async fn send_edu_servers<'a, S>(&self, servers: S, serialized: EduBuf) -> R
 
 	#[tracing::instrument(skip(self, room_id), level = "debug")]
 	pub async fn flush_room(&self, room_id: &RoomId) -> Result<()> {
-		let servers = self
+		let servers = self.room_destinations(room_id).await;
+
+		self.flush_servers(servers).await
+	}
+
+	/// Remote servers in the room which are not denied by its m.room.server_acl;
+	/// none if the room does not federate
+	async fn room_destinations<'a>(
+		&'a self,
+		room_id: &'a RoomId,
+	) -> impl Stream<Item = &'a ServerName> + Send + 'a {
+		let federated = self.services.state_accessor.is_federated(room_id).await;
+
+		let acl = self
 			.services
+			.state_accessor
+			.room_state_get_content(room_id, &StateEventType::RoomServerAcl, "")
+			.await
+			.ok()
+			.filter(|acl: &RoomServerAclEventContent| !acl.allow.is_empty());
+
+		self.services
 			.state_cache
 			.room_servers(room_id)
-			.ready_filter(|server_name| !self.services.globals.server_is_ours(server_name));
-
-		self.flush_servers(servers).await
+			.ready_filter(move |_| federated)
+			.ready_filter(|server_name| !self.services.globals.server_is_ours(server_name))
+			.ready_filter(move |server_name| {
+				acl.as_ref().is_none_or(|acl| acl.is_allowed(server_name))
+			})
 	}
 
 	#[tracing::instrument(skip(self, servers), level = "debug")]
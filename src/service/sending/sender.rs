@@ -49,6 +49,7 @@
 	OwnedUserId, RoomId, RoomVersionId, ServerName, UInt,
 };
 use serde_json::value::{to_raw_value, RawValue as RawJsonValue};
+use tokio::time::MissedTickBehavior;
 
 use super::{
 	appservice, data::QueueItem, Destination, EduBuf, EduVec, Msg, SendingEvent, Service,
@@ -71,6 +72,7 @@ enum TransactionStatus {
 const SELECT_RECEIPT_LIMIT: usize = 256;
 const SELECT_EDU_LIMIT: usize = EDU_LIMIT - 2;
 const DEQUEUE_LIMIT: usize = 48;
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
 
 pub const PDU_LIMIT: usize = 50;
 pub const EDU_LIMIT: usize = 100;
@@ -115,6 +117,8 @@ async fn work_loop<'a>(
 			.map(|(_, receiver)| receiver.clone())
 			.expect("Missing channel for sender worker");
 
+		let mut retry = tokio::time::interval(RETRY_INTERVAL);
+		retry.set_missed_tick_behavior(MissedTickBehavior::Delay);
 		while !receiver.is_closed() {
 			tokio::select! {
 				Some(response) = futures.next() => {
@@ -124,6 +128,40 @@ async fn work_loop<'a>(
 					Ok(request) => self.handle_request(request, futures, statuses).await,
 					Err(_) => return,
 				},
+				_ = retry.tick() => {
+					self.handle_retries(futures, statuses).await;
+				},
+			}
+		}
+	}
+
+	/// Failed destinations are otherwise only retried when new events are
+	/// queued for them; resume any whose backoff has elapsed.
+	#[tracing::instrument(name = "retry", level = "trace", skip_all)]
+	async fn handle_retries<'a>(
+		&'a self,
+		futures: &mut SendingFutures<'a>,
+		statuses: &mut CurTransactionStatus,
+	) {
+		let min = self.server.config.sender_timeout;
+		let max = self.server.config.sender_retry_backoff_limit;
+		let ready: Vec<_> = statuses
+			.iter()
+			.filter_map(|(dest, status)| match status {
+				| TransactionStatus::Failed(tries, time)
+					if !continue_exponential_backoff_secs(min, max, time.elapsed(), *tries) =>
+					Some(dest.clone()),
+				| _ => None,
+			})
+			.collect();
+
+		for dest in ready {
+			if let Ok(Some(events)) = self.select_events(&dest, Vec::new(), statuses).await {
+				if !events.is_empty() {
+					futures.push(self.send_events(dest, events));
+				} else {
+					statuses.remove(&dest);
+				}
 			}
 		}
 	}
@@ -263,6 +301,30 @@ async fn startup_netburst<'a>(
 			}
 		}
 
+		// Destinations with nothing in flight but a persisted queue would otherwise
+		// wait for new traffic to them before delivery resumes.
+		if self.server.config.startup_netburst {
+			let mut queued = HashMap::<Destination, Vec<QueueItem>>::new();
+			let mut pending = self.db.all_queued_requests().boxed();
+			while let Some((key, event, dest)) = pending.next().await {
+				if self.shard_id(&dest) != id || txns.contains_key(&dest) {
+					continue;
+				}
+
+				let entry = queued.entry(dest).or_default();
+				if entry.len() < DEQUEUE_LIMIT {
+					entry.push((key, event));
+				}
+			}
+
+			for (dest, items) in queued {
+				self.db.mark_as_active(items.iter());
+				txns.entry(dest)
+					.or_default()
+					.extend(items.into_iter().map(|(_, event)| event));
+			}
+		}
+
 		for (dest, events) in txns {
 			if self.server.config.startup_netburst && !events.is_empty() {
 				statuses.insert(dest.clone(), TransactionStatus::Running);
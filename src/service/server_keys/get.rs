@@ -68,8 +68,13 @@ pub async fn get_verify_key(
 	let notary_first = self.services.server.config.query_trusted_key_servers_first;
 	let notary_only = self.services.server.config.only_query_trusted_key_servers;
 
-	if let Some(result) = self.verify_keys_for(origin).await.remove(key_id) {
-		return Ok(result);
+	// Keys past their valid_until_ts are refreshed first; the stale key is only
+	// used as a last resort if the refresh fails.
+	let cached = self.verify_keys_for(origin).await.remove(key_id);
+	if let Some(result) = &cached {
+		if self.signing_keys_valid(origin).await {
+			return Ok(result.clone());
+		}
 	}
 
 	if notary_first {
@@ -90,6 +95,10 @@ pub async fn get_verify_key(
 		}
 	}
 
+	if let Some(result) = cached {
+		return Ok(result);
+	}
+
 	Err!(BadServerResponse(debug_error!(
 		?key_id,
 		?origin,
@@ -1,11 +1,16 @@
 mod acquire;
 mod get;
 mod keypair;
+mod notary;
 mod request;
 mod sign;
 mod verify;
 
-use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use std::{
+	collections::BTreeMap,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
 
 use conduwuit::{
 	implement,
@@ -14,12 +19,13 @@
 };
 use database::{Deserialized, Json, Map};
 use futures::StreamExt;
+use lru_cache::LruCache;
 use ruma::{
 	api::federation::discovery::{ServerSigningKeys, VerifyKey},
 	serde::Raw,
 	signatures::{Ed25519KeyPair, PublicKeyMap, PublicKeySet},
-	CanonicalJsonObject, MilliSecondsSinceUnixEpoch, OwnedServerSigningKeyId, RoomVersionId,
-	ServerName, ServerSigningKeyId,
+	CanonicalJsonObject, MilliSecondsSinceUnixEpoch, OwnedServerName, OwnedServerSigningKeyId,
+	RoomVersionId, ServerName, ServerSigningKeyId,
 };
 use serde_json::value::RawValue as RawJsonValue;
 
@@ -29,6 +35,7 @@ pub struct Service {
 	keypair: Box<Ed25519KeyPair>,
 	verify_keys: VerifyKeys,
 	minimum_valid: Duration,
+	notary_cache: Mutex<LruCache<OwnedServerName, ServerSigningKeys>>,
 	services: Services,
 	db: Data,
 }
@@ -43,6 +50,14 @@ struct Data {
 	server_signingkeys: Arc<Map>,
 }
 
+/// Events at least this large (in serialized bytes) are hashed and verified on
+/// a blocking thread. Below it the work takes about as long as handing it off
+/// to the blocking pool and cloning the event there.
+pub const BLOCKING_EVENT_LEN: usize = 16 * 1024;
+
+/// How many servers' signing keys are kept for answering notary queries.
+const NOTARY_CACHE_CAPACITY: usize = 1024;
+
 pub type VerifyKeys = BTreeMap<OwnedServerSigningKeyId, VerifyKey>;
 pub type PubKeyMap = PublicKeyMap;
 pub type PubKeys = PublicKeySet;
@@ -58,6 +73,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 			keypair,
 			verify_keys,
 			minimum_valid,
+			notary_cache: Mutex::new(LruCache::new(NOTARY_CACHE_CAPACITY)),
 			services: Services {
 				globals: args.depend::<globals::Service>("globals"),
 				sending: args.depend::<sending::Service>("sending"),
@@ -69,6 +85,8 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		}))
 	}
 
+	fn clear_cache(&self) { self.notary_cache.lock().expect("locked").clear(); }
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
@@ -107,6 +125,7 @@ async fn add_signing_keys(&self, new_keys: ServerSigningKeys) {
 			ServerSigningKeys::new(origin.to_owned(), MilliSecondsSinceUnixEpoch::now())
 		});
 
+	keys.valid_until_ts = keys.valid_until_ts.max(new_keys.valid_until_ts);
 	keys.verify_keys.extend(new_keys.verify_keys);
 	keys.old_verify_keys.extend(new_keys.old_verify_keys);
 	self.db.server_signingkeys.raw_put(origin, Json(&keys));
@@ -176,6 +195,19 @@ pub async fn verify_keys_for(&self, origin: &ServerName) -> VerifyKeys {
 	keys
 }
 
+/// Whether the cached signing keys for the origin are still within their
+/// valid_until_ts; our own keys are always considered valid.
+#[implement(Service)]
+pub async fn signing_keys_valid(&self, origin: &ServerName) -> bool {
+	if self.services.globals.server_is_ours(origin) {
+		return true;
+	}
+
+	self.signing_keys_for(origin)
+		.await
+		.is_ok_and(|keys| keys.valid_until_ts > MilliSecondsSinceUnixEpoch::now())
+}
+
 #[implement(Service)]
 pub async fn signing_keys_for(&self, origin: &ServerName) -> Result<ServerSigningKeys> {
 	self.db.server_signingkeys.get(origin).await.deserialized()
@@ -0,0 +1,53 @@
+use conduwuit::{debug_warn, err, implement, utils, Result};
+use ruma::{
+	api::federation::discovery::ServerSigningKeys, MilliSecondsSinceUnixEpoch, ServerName,
+};
+
+use super::{PubKeyMap, PubKeys};
+
+/// The signing keys of another server, with its self-signature intact, for
+/// answering notary queries. Keys fetched earlier are served as long as they
+/// are valid until `minimum_valid_until_ts`; only otherwise are they fetched
+/// from the server again.
+#[implement(super::Service)]
+pub async fn notary_keys(
+	&self,
+	server_name: &ServerName,
+	minimum_valid_until_ts: MilliSecondsSinceUnixEpoch,
+) -> Result<ServerSigningKeys> {
+	let cached = self
+		.notary_cache
+		.lock()
+		.expect("locked")
+		.get_mut(server_name)
+		.filter(|keys| keys.valid_until_ts >= minimum_valid_until_ts)
+		.cloned();
+
+	if let Some(server_key) = cached {
+		return Ok(server_key);
+	}
+
+	let server_key = self.server_request(server_name).await?;
+	let keys: PubKeys = server_key
+		.verify_keys
+		.iter()
+		.map(|(key_id, key)| (key_id.to_string(), key.key.clone()))
+		.collect();
+
+	let keys: PubKeyMap = [(server_name.as_str().into(), keys)].into();
+	let object = utils::to_canonical_object(&server_key)
+		.map_err(|e| err!(BadServerResponse("Invalid server keys for {server_name}: {e}")))?;
+
+	ruma::signatures::verify_json(&keys, object).map_err(|e| {
+		err!(BadServerResponse(debug_warn!(
+			"Server keys for {server_name} are not signed by the server: {e}"
+		)))
+	})?;
+
+	self.notary_cache
+		.lock()
+		.expect("locked")
+		.insert(server_name.to_owned(), server_key.clone());
+
+	Ok(server_key)
+}
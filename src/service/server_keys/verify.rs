@@ -54,7 +54,18 @@ pub async fn verify_event(
 ) -> Result<Verified> {
 	let room_version = room_version.unwrap_or(&RoomVersionId::V11);
 	let keys = self.get_event_keys(event, room_version).await?;
-	ruma::signatures::verify_event(&keys, event, room_version).map_err(Into::into)
+	if estimated_len(event) < super::BLOCKING_EVENT_LEN {
+		return ruma::signatures::verify_event(&keys, event, room_version).map_err(Into::into);
+	}
+
+	// Hashing large events is CPU-bound; keep it off the executor.
+	let (event, room_version) = (event.clone(), room_version.clone());
+	self.services
+		.server
+		.runtime()
+		.spawn_blocking(move || ruma::signatures::verify_event(&keys, &event, &room_version))
+		.await?
+		.map_err(Into::into)
 }
 
 #[implement(super::Service)]
@@ -65,5 +76,36 @@ pub async fn verify_json(
 ) -> Result {
 	let room_version = room_version.unwrap_or(&RoomVersionId::V11);
 	let keys = self.get_event_keys(event, room_version).await?;
-	ruma::signatures::verify_json(&keys, event.clone()).map_err(Into::into)
+	let event = event.clone();
+	if estimated_len(&event) < super::BLOCKING_EVENT_LEN {
+		return ruma::signatures::verify_json(&keys, event).map_err(Into::into);
+	}
+
+	self.services
+		.server
+		.runtime()
+		.spawn_blocking(move || ruma::signatures::verify_json(&keys, event))
+		.await?
+		.map_err(Into::into)
+}
+
+/// Approximates the serialized length of an object without serializing it.
+fn estimated_len(object: &CanonicalJsonObject) -> usize {
+	fn value_len(value: &CanonicalJsonValue) -> usize {
+		match value {
+			| CanonicalJsonValue::String(string) => string.len().saturating_add(2),
+			| CanonicalJsonValue::Array(array) => array
+				.iter()
+				.map(value_len)
+				.fold(2, |len, value| len.saturating_add(value).saturating_add(1)),
+			| CanonicalJsonValue::Object(object) => estimated_len(object),
+			| _ => 8,
+		}
+	}
+
+	object.iter().fold(2, |len, (key, value)| {
+		len.saturating_add(key.len())
+			.saturating_add(value_len(value))
+			.saturating_add(4)
+	})
 }
@@ -0,0 +1,318 @@
+use std::{
+	collections::BTreeMap,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+};
+
+use async_trait::async_trait;
+use conduwuit::{debug, err, error, pdu::PduBuilder, utils::MutexMap, Err, Result, Server};
+use database::{Deserialized, Map};
+use ruma::{
+	events::{
+		room::{
+			create::RoomCreateEventContent,
+			guest_access::{GuestAccess, RoomGuestAccessEventContent},
+			history_visibility::{HistoryVisibility, RoomHistoryVisibilityEventContent},
+			join_rules::{JoinRule, RoomJoinRulesEventContent},
+			member::{MembershipState, RoomMemberEventContent},
+			message::RoomMessageEventContent,
+			name::RoomNameEventContent,
+			power_levels::RoomPowerLevelsEventContent,
+		},
+		tag::{TagEvent, TagEventContent, TagInfo},
+		RoomAccountDataEventType,
+	},
+	OwnedEventId, OwnedRoomId, OwnedUserId, RoomId, RoomVersionId, UserId,
+};
+
+use crate::{account_data, globals, rooms, users, Dep};
+
+/// Room tag clients use to present the server notices room as a system room.
+pub const SERVER_NOTICE_TAG: &str = "m.server_notice";
+
+/// Records the notices user this service created, in the `global` map.
+const SERVER_NOTICES_USER: &[u8] = b"server_notices_user";
+
+pub struct Service {
+	/// The user server notices are sent from, if configured.
+	notices_user: Option<OwnedUserId>,
+	/// Set once the notices user is known to be the account this service
+	/// created, never one registered by someone else.
+	enabled: AtomicBool,
+	/// Serializes finding or creating each recipient's notices room.
+	room_mutex: MutexMap<OwnedUserId, ()>,
+	db: Data,
+	services: Services,
+}
+
+struct Data {
+	global: Arc<Map>,
+	userid_servernoticesroomid: Arc<Map>,
+}
+
+struct Services {
+	server: Arc<Server>,
+	account_data: Dep<account_data::Service>,
+	globals: Dep<globals::Service>,
+	short: Dep<rooms::short::Service>,
+	state: Dep<rooms::state::Service>,
+	state_cache: Dep<rooms::state_cache::Service>,
+	timeline: Dep<rooms::timeline::Service>,
+	users: Dep<users::Service>,
+}
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		let notices_user = args
+			.server
+			.config
+			.server_notices_user
+			.as_ref()
+			.map(|localpart| {
+				UserId::parse_with_server_name(localpart.as_str(), &args.server.name)
+			})
+			.transpose()
+			.map_err(|e| err!(Config("server_notices_user", "Invalid localpart: {e}")))?;
+
+		Ok(Arc::new(Self {
+			notices_user,
+			enabled: AtomicBool::new(false),
+			room_mutex: MutexMap::new(),
+			db: Data {
+				global: args.db["global"].clone(),
+				userid_servernoticesroomid: args.db["userid_servernoticesroomid"].clone(),
+			},
+			services: Services {
+				server: args.server.clone(),
+				account_data: args.depend::<account_data::Service>("account_data"),
+				globals: args.depend::<globals::Service>("globals"),
+				short: args.depend::<rooms::short::Service>("rooms::short"),
+				state: args.depend::<rooms::state::Service>("rooms::state"),
+				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
+				timeline: args.depend::<rooms::timeline::Service>("rooms::timeline"),
+				users: args.depend::<users::Service>("users"),
+			},
+		}))
+	}
+
+	async fn worker(self: Arc<Self>) -> Result {
+		let Some(notices_user) = &self.notices_user else {
+			return Ok(());
+		};
+
+		let created: Option<OwnedUserId> = self
+			.db
+			.global
+			.get(SERVER_NOTICES_USER)
+			.await
+			.deserialized()
+			.ok();
+
+		if created.as_ref() == Some(notices_user) {
+			self.enabled.store(true, Ordering::Release);
+			return Ok(());
+		}
+
+		// Never send notices as an account someone else registered
+		if self.services.users.exists(notices_user).await {
+			error!(
+				?notices_user,
+				"The server_notices_user account already exists and was not created for server \
+				 notices. Server notices are disabled; configure an unused localpart."
+			);
+			return Ok(());
+		}
+
+		if self.services.globals.is_read_only() {
+			debug!("Not creating the server notices user on a read-only database");
+			return Ok(());
+		}
+
+		// Create the notices user up front so its localpart can't be registered
+		self.services.users.create(notices_user, None)?;
+		self.services.users.set_displayname(
+			notices_user,
+			Some(self.services.server.config.server_notices_room_name.clone()),
+		);
+
+		self.db
+			.global
+			.insert(SERVER_NOTICES_USER, notices_user.as_bytes());
+
+		self.enabled.store(true, Ordering::Release);
+
+		Ok(())
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+impl Service {
+	/// Sends a server notice to a local user, creating their server notices
+	/// room if they don't have one they are joined or invited to.
+	pub async fn send_notice(
+		&self,
+		user_id: &UserId,
+		content: RoomMessageEventContent,
+	) -> Result<OwnedEventId> {
+		let Some(notices_user) = &self.notices_user else {
+			return Err!("Server notices are disabled, set server_notices_user to enable them.");
+		};
+
+		if !self.enabled.load(Ordering::Acquire) {
+			return Err!("Server notices are disabled; check the server log for the cause.");
+		}
+
+		if !self.services.globals.user_is_local(user_id) {
+			return Err!(Request(InvalidParam(
+				"Server notices can only be sent to local users."
+			)));
+		}
+
+		let room_lock = self.room_mutex.lock(user_id).await;
+		let room_id = match self.get_notices_room(user_id).await {
+			| Some(room_id) => room_id,
+			| None => self.create_notices_room(notices_user, user_id).await?,
+		};
+		drop(room_lock);
+
+		let state_lock = self.services.state.mutex.lock(&room_id).await;
+
+		self.services
+			.timeline
+			.build_and_append_pdu(
+				PduBuilder::timeline(&content),
+				notices_user,
+				&room_id,
+				&state_lock,
+			)
+			.await
+	}
+
+	/// The user's server notices room, if they are still joined or invited to
+	/// it.
+	pub async fn get_notices_room(&self, user_id: &UserId) -> Option<OwnedRoomId> {
+		let room_id: OwnedRoomId = self
+			.db
+			.userid_servernoticesroomid
+			.get(user_id)
+			.await
+			.deserialized()
+			.ok()?;
+
+		let state_cache = &self.services.state_cache;
+		let in_room = state_cache.is_joined(user_id, &room_id).await
+			|| state_cache.is_invited(user_id, &room_id).await;
+
+		in_room.then_some(room_id)
+	}
+
+	async fn create_notices_room(
+		&self,
+		notices_user: &UserId,
+		user_id: &UserId,
+	) -> Result<OwnedRoomId> {
+		let room_id = RoomId::new(self.services.globals.server_name());
+		let room_version = &self.services.server.config.default_room_version;
+
+		let _short_id = self
+			.services
+			.short
+			.get_or_create_shortroomid(&room_id)
+			.await;
+
+		let state_lock = self.services.state.mutex.lock(&room_id).await;
+
+		let create_content = {
+			use RoomVersionId::*;
+			match room_version {
+				| V1 | V2 | V3 | V4 | V5 | V6 | V7 | V8 | V9 | V10 =>
+					RoomCreateEventContent::new_v1(notices_user.to_owned()),
+				| _ => RoomCreateEventContent::new_v11(),
+			}
+		};
+
+		// Only the notices user may send messages, the recipient can still leave
+		let users = BTreeMap::from_iter([(notices_user.to_owned(), 100.into())]);
+
+		let room_name = self.services.server.config.server_notices_room_name.clone();
+
+		let pdus = [
+			PduBuilder::state(String::new(), &RoomCreateEventContent {
+				federate: false,
+				predecessor: None,
+				room_version: room_version.clone(),
+				..create_content
+			}),
+			PduBuilder::state(
+				notices_user.to_string(),
+				&RoomMemberEventContent::new(MembershipState::Join),
+			),
+			PduBuilder::state(String::new(), &RoomPowerLevelsEventContent {
+				users,
+				events_default: 100.into(),
+				..Default::default()
+			}),
+			PduBuilder::state(String::new(), &RoomJoinRulesEventContent::new(JoinRule::Invite)),
+			PduBuilder::state(
+				String::new(),
+				&RoomHistoryVisibilityEventContent::new(HistoryVisibility::Shared),
+			),
+			PduBuilder::state(
+				String::new(),
+				&RoomGuestAccessEventContent::new(GuestAccess::Forbidden),
+			),
+			PduBuilder::state(String::new(), &RoomNameEventContent::new(room_name)),
+			PduBuilder::state(
+				user_id.to_string(),
+				&RoomMemberEventContent::new(MembershipState::Invite),
+			),
+		];
+
+		for pdu in pdus {
+			self.services
+				.timeline
+				.build_and_append_pdu(pdu, notices_user, &room_id, &state_lock)
+				.await?;
+		}
+
+		self.db
+			.userid_servernoticesroomid
+			.insert(user_id.as_bytes(), room_id.as_bytes());
+
+		if let Err(e) = self.set_notices_tag(&room_id, user_id).await {
+			error!(?room_id, ?user_id, ?e, "Failed to tag server notices room");
+		}
+
+		Ok(room_id)
+	}
+
+	async fn set_notices_tag(&self, room_id: &RoomId, user_id: &UserId) -> Result {
+		let mut event = self
+			.services
+			.account_data
+			.get_room(room_id, user_id, RoomAccountDataEventType::Tag)
+			.await
+			.unwrap_or_else(|_| TagEvent {
+				content: TagEventContent { tags: BTreeMap::new() },
+			});
+
+		event
+			.content
+			.tags
+			.insert(SERVER_NOTICE_TAG.to_owned().into(), TagInfo::new());
+
+		self.services
+			.account_data
+			.update(
+				Some(room_id),
+				user_id,
+				RoomAccountDataEventType::Tag,
+				&serde_json::to_value(event)?,
+			)
+			.await
+	}
+}
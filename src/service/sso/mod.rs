@@ -0,0 +1,356 @@
+use std::{
+	collections::HashMap,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use conduwuit::{config::SsoProviderConfig, debug_warn, err, implement, utils, Err, Result};
+use database::{Deserialized, Map};
+use ruma::{OwnedUserId, UserId};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::RwLock;
+use url::Url;
+
+use crate::{client, config, globals, jwt, Dep};
+
+pub struct Service {
+	pending: Mutex<HashMap<String, Pending>>,
+	confirmations: Mutex<HashMap<String, Confirmation>>,
+	discovery: RwLock<HashMap<String, (Instant, Discovery)>>,
+	db: Data,
+	services: Services,
+}
+
+struct Data {
+	idpsub_userid: Arc<Map>,
+}
+
+struct Services {
+	client: Dep<client::Service>,
+	config: Dep<config::Service>,
+	globals: Dep<globals::Service>,
+	jwt: Dep<jwt::Service>,
+}
+
+/// An identity asserted by an identity provider at the end of a login.
+pub struct Identity {
+	pub idp_id: String,
+	pub subject: String,
+	pub localpart: Option<String>,
+	pub displayname: Option<String>,
+	pub session: String,
+	pub redirect_url: Url,
+	pub allow_registration: bool,
+}
+
+/// A login which was sent to an identity provider and hasn't come back yet.
+struct Pending {
+	idp_id: String,
+	nonce: String,
+	session: String,
+	redirect_url: Url,
+	started: Instant,
+}
+
+/// A completed login waiting for the user to confirm they want to be sent to
+/// the client's redirect URL.
+struct Confirmation {
+	user_id: OwnedUserId,
+	session: String,
+	redirect_url: Url,
+	started: Instant,
+}
+
+#[derive(Clone, Deserialize)]
+struct Discovery {
+	issuer: String,
+	authorization_endpoint: Url,
+	token_endpoint: Url,
+	jwks_uri: Url,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+	id_token: String,
+}
+
+/// Path of the callback identity providers redirect users back to.
+pub const CALLBACK_PATH: &str = "/_conduwuit/sso/callback";
+
+/// Path of the page users confirm the client they are sent to on.
+pub const CONFIRM_PATH: &str = "/_conduwuit/sso/confirm";
+
+/// Cookie tying the callback to the browser which started the login.
+pub const SESSION_COOKIE: &str = "conduwuit_sso_session";
+
+/// How long users have to complete a login at the identity provider, and to
+/// confirm it afterwards.
+pub const LOGIN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// How long a provider's discovery document is used before it is refetched.
+const DISCOVERY_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+const STATE_LENGTH: usize = 32;
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			pending: Mutex::new(HashMap::new()),
+			confirmations: Mutex::new(HashMap::new()),
+			discovery: RwLock::new(HashMap::new()),
+			db: Data {
+				idpsub_userid: args.db["idpsub_userid"].clone(),
+			},
+			services: Services {
+				client: args.depend::<client::Service>("client"),
+				config: args.depend::<config::Service>("config"),
+				globals: args.depend::<globals::Service>("globals"),
+				jwt: args.depend::<jwt::Service>("jwt"),
+			},
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// The configured identity providers.
+#[implement(Service)]
+#[must_use]
+pub fn providers(&self) -> &[SsoProviderConfig] { &self.services.config.sso_providers }
+
+/// Starts a login with an identity provider, or the first configured one if
+/// none is given. Returns the URL the user has to be sent to, and the session
+/// to store in the user's browser as the `SESSION_COOKIE`.
+#[implement(Service)]
+pub async fn start_login(
+	&self,
+	idp_id: Option<&str>,
+	redirect_url: &str,
+) -> Result<(Url, String)> {
+	let redirect_url = Url::parse(redirect_url)
+		.map_err(|e| err!(Request(InvalidParam("Invalid redirect URL: {e}"))))?;
+
+	let allowlist = &self.services.config.sso_redirect_allowlist;
+	let allowed = redirect_url
+		.host_str()
+		.is_some_and(|host| allowlist.iter().any(|allowed| allowed == host));
+
+	if !allowlist.is_empty() && !allowed {
+		return Err!(Request(Forbidden("Redirect URL is not allowed.")));
+	}
+
+	let provider = self.provider(idp_id)?;
+	let discovery = self.discover(provider).await?;
+	let state = utils::random_string(STATE_LENGTH);
+	let nonce = utils::random_string(STATE_LENGTH);
+	let session = utils::random_string(STATE_LENGTH);
+
+	let mut scopes = provider.scopes.clone();
+	if !scopes.iter().any(|scope| scope == "openid") {
+		scopes.insert(0, "openid".to_owned());
+	}
+
+	let mut url = discovery.authorization_endpoint;
+	url.query_pairs_mut()
+		.append_pair("response_type", "code")
+		.append_pair("client_id", &provider.client_id)
+		.append_pair("redirect_uri", self.callback_url()?.as_str())
+		.append_pair("scope", &scopes.join(" "))
+		.append_pair("state", &state)
+		.append_pair("nonce", &nonce);
+
+	let mut pending = self.pending.lock().expect("locked");
+	pending.retain(|_, login| login.started.elapsed() < LOGIN_TIMEOUT);
+	pending.insert(state, Pending {
+		idp_id: provider.id.clone(),
+		nonce,
+		session: session.clone(),
+		redirect_url,
+		started: Instant::now(),
+	});
+
+	Ok((url, session))
+}
+
+/// Completes a login when the identity provider redirects the user back,
+/// exchanging the authorization code for the user's ID token. The session
+/// must be the one from the browser which started the login.
+#[implement(Service)]
+pub async fn complete_login(
+	&self,
+	state: &str,
+	code: &str,
+	session: Option<&str>,
+) -> Result<Identity> {
+	let pending = self
+		.pending
+		.lock()
+		.expect("locked")
+		.remove(state)
+		.filter(|login| login.started.elapsed() < LOGIN_TIMEOUT)
+		.filter(|login| session == Some(login.session.as_str()))
+		.ok_or_else(|| err!(Request(Forbidden("Unknown or expired login session."))))?;
+
+	let provider = self.provider(Some(&pending.idp_id))?;
+	let discovery = self.discover(provider).await?;
+	let callback_url = self.callback_url()?;
+
+	let body = self
+		.services
+		.client
+		.default
+		.post(discovery.token_endpoint.clone())
+		.form(&[
+			("grant_type", "authorization_code"),
+			("code", code),
+			("redirect_uri", callback_url.as_str()),
+			("client_id", &provider.client_id),
+			("client_secret", &provider.client_secret),
+		])
+		.send()
+		.await?
+		.error_for_status()?
+		.bytes()
+		.await?;
+
+	let response: TokenResponse = serde_json::from_slice(&body).map_err(|e| {
+		err!(BadServerResponse(debug_warn!(
+			"Invalid token response from identity provider: {e}"
+		)))
+	})?;
+
+	let claims = self
+		.services
+		.jwt
+		.verify(&response.id_token, Some(&provider.client_secret), Some(&discovery.jwks_uri))
+		.await?;
+
+	let claim = |name: &str| claims.other.get(name).and_then(Value::as_str);
+	let audience_matches = match claims.other.get("aud") {
+		| Some(Value::String(aud)) => *aud == provider.client_id,
+		| Some(Value::Array(aud)) => aud.iter().any(|aud| *aud == *provider.client_id),
+		| _ => false,
+	};
+
+	if claim("iss") != Some(discovery.issuer.as_str())
+		|| !audience_matches
+		|| claim("nonce") != Some(pending.nonce.as_str())
+	{
+		return Err!(Request(Forbidden("ID token was not issued for this login.")));
+	}
+
+	Ok(Identity {
+		idp_id: pending.idp_id,
+		localpart: claim(&provider.localpart_claim).map(ToOwned::to_owned),
+		displayname: claim("name").map(ToOwned::to_owned),
+		subject: claims.sub,
+		session: pending.session,
+		redirect_url: pending.redirect_url,
+		allow_registration: provider.allow_registration,
+	})
+}
+
+/// Holds a completed login until the user confirms they want to be sent to
+/// the client's redirect URL. Returns the ID of the confirmation.
+#[implement(Service)]
+pub fn await_confirmation(&self, user_id: OwnedUserId, identity: Identity) -> String {
+	let id = utils::random_string(STATE_LENGTH);
+	let mut confirmations = self.confirmations.lock().expect("locked");
+	confirmations.retain(|_, confirmation| confirmation.started.elapsed() < LOGIN_TIMEOUT);
+	confirmations.insert(id.clone(), Confirmation {
+		user_id,
+		session: identity.session,
+		redirect_url: identity.redirect_url,
+		started: Instant::now(),
+	});
+
+	id
+}
+
+/// Takes a confirmed login, returning the user and where to send them. The
+/// session must be the one from the browser which started the login.
+#[implement(Service)]
+pub fn confirm_login(&self, id: &str, session: Option<&str>) -> Result<(OwnedUserId, Url)> {
+	self.confirmations
+		.lock()
+		.expect("locked")
+		.remove(id)
+		.filter(|confirmation| confirmation.started.elapsed() < LOGIN_TIMEOUT)
+		.filter(|confirmation| session == Some(confirmation.session.as_str()))
+		.map(|confirmation| (confirmation.user_id, confirmation.redirect_url))
+		.ok_or_else(|| err!(Request(Forbidden("Unknown or expired login session."))))
+}
+
+/// The local user an identity provider's subject was mapped to.
+#[implement(Service)]
+pub async fn user_for_subject(&self, idp_id: &str, subject: &str) -> Result<OwnedUserId> {
+	self.db
+		.idpsub_userid
+		.qry(&(idp_id, subject))
+		.await
+		.deserialized()
+}
+
+/// Maps an identity provider's subject to a local user.
+#[implement(Service)]
+pub fn set_user_for_subject(&self, idp_id: &str, subject: &str, user_id: &UserId) {
+	self.db.idpsub_userid.put((idp_id, subject), user_id);
+}
+
+#[implement(Service)]
+fn provider(&self, idp_id: Option<&str>) -> Result<&SsoProviderConfig> {
+	let mut providers = self.providers().iter();
+	match idp_id {
+		| Some(idp_id) => providers.find(|provider| provider.id == idp_id),
+		| None => providers.next(),
+	}
+	.ok_or_else(|| err!(Request(NotFound("Unknown identity provider."))))
+}
+
+#[implement(Service)]
+async fn discover(&self, provider: &SsoProviderConfig) -> Result<Discovery> {
+	if let Some((fetched, discovery)) = self.discovery.read().await.get(&provider.id) {
+		if fetched.elapsed() < DISCOVERY_CACHE_TTL {
+			return Ok(discovery.clone());
+		}
+	}
+
+	let issuer = &provider.issuer;
+	let mut url = issuer.clone();
+	url.path_segments_mut()
+		.map_err(|()| err!(Config("sso_providers", "Invalid issuer URL {issuer}")))?
+		.pop_if_empty()
+		.extend([".well-known", "openid-configuration"]);
+
+	let body = self
+		.services
+		.client
+		.default
+		.get(url)
+		.send()
+		.await?
+		.error_for_status()?
+		.bytes()
+		.await?;
+
+	let discovery: Discovery = serde_json::from_slice(&body).map_err(|e| {
+		err!(BadServerResponse(debug_warn!("Invalid OpenID Connect discovery document: {e}")))
+	})?;
+
+	self.discovery
+		.write()
+		.await
+		.insert(provider.id.clone(), (Instant::now(), discovery.clone()));
+
+	Ok(discovery)
+}
+
+#[implement(Service)]
+fn callback_url(&self) -> Result<Url> {
+	self.services
+		.globals
+		.client_url()?
+		.join(CALLBACK_PATH)
+		.map_err(|e| err!("Failed to build the SSO callback URL: {e}"))
+}
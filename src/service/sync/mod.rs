@@ -30,6 +30,7 @@ pub struct Data {
 	userroomid_joined: Arc<Map>,
 	userroomid_invitestate: Arc<Map>,
 	userroomid_leftstate: Arc<Map>,
+	userroomid_knockedstate: Arc<Map>,
 	userroomid_notificationcount: Arc<Map>,
 	userroomid_highlightcount: Arc<Map>,
 	pduid_pdu: Arc<Map>,
@@ -76,6 +77,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				userroomid_joined: args.db["userroomid_joined"].clone(),
 				userroomid_invitestate: args.db["userroomid_invitestate"].clone(),
 				userroomid_leftstate: args.db["userroomid_leftstate"].clone(),
+				userroomid_knockedstate: args.db["userroomid_knockedstate"].clone(),
 				userroomid_notificationcount: args.db["userroomid_notificationcount"].clone(),
 				userroomid_highlightcount: args.db["userroomid_highlightcount"].clone(),
 				pduid_pdu: args.db["pduid_pdu"].clone(),
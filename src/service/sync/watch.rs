@@ -22,6 +22,7 @@ pub async fn watch(&self, user_id: &UserId, device_id: &DeviceId) -> Result {
 	futures.push(self.db.userroomid_joined.watch_prefix(&userid_prefix));
 	futures.push(self.db.userroomid_invitestate.watch_prefix(&userid_prefix));
 	futures.push(self.db.userroomid_leftstate.watch_prefix(&userid_prefix));
+	futures.push(self.db.userroomid_knockedstate.watch_prefix(&userid_prefix));
 	futures.push(
 		self.db
 			.userroomid_notificationcount
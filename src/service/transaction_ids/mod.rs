@@ -1,21 +1,32 @@
 use std::sync::Arc;
 
-use conduwuit::{implement, Result};
+use conduwuit::{
+	err, implement,
+	utils::{self, stream::TryIgnore, MutexMap},
+	Result,
+};
 use database::{Handle, Map};
-use ruma::{DeviceId, TransactionId, UserId};
+use futures::StreamExt;
+use ruma::{DeviceId, ServerName, TransactionId, UserId};
 
 pub struct Service {
+	/// Serializes the processing of a federation transaction, keyed by
+	/// `server_txnid_key`, so retries wait for and replay the first response.
+	pub mutex_server_txnid: MutexMap<String, ()>,
 	db: Data,
 }
 
 struct Data {
+	servertxnid_response: Arc<Map>,
 	userdevicetxnid_response: Arc<Map>,
 }
 
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
+			mutex_server_txnid: MutexMap::new(),
 			db: Data {
+				servertxnid_response: args.db["servertxnid_response"].clone(),
 				userdevicetxnid_response: args.db["userdevicetxnid_response"].clone(),
 			},
 		}))
@@ -52,3 +63,68 @@ pub async fn existing_txnid(
 	let key = (user_id, device_id, txn_id);
 	self.db.userdevicetxnid_response.qry(&key).await
 }
+
+/// How many of the most recent transaction responses are kept per origin.
+/// Servers retry a transaction until it succeeds before sending the next one,
+/// so only the latest few are ever replayed.
+const SERVER_TXNID_LIMIT: usize = 32;
+
+/// Stores the response to a federation transaction, prefixed with the time it
+/// was stored, and forgets the oldest responses of the origin beyond
+/// `SERVER_TXNID_LIMIT`.
+#[implement(Service)]
+pub async fn add_server_txnid(&self, origin: &ServerName, txn_id: &TransactionId, data: &[u8]) {
+	let mut prefix = origin.as_bytes().to_vec();
+	prefix.push(0xFF);
+
+	let mut key = prefix.clone();
+	key.extend_from_slice(txn_id.as_bytes());
+
+	let mut value = utils::millis_since_unix_epoch().to_be_bytes().to_vec();
+	value.extend_from_slice(data);
+
+	self.db.servertxnid_response.insert(&key, &value);
+
+	let mut stored: Vec<(u64, Vec<u8>)> = self
+		.db
+		.servertxnid_response
+		.raw_stream_prefix(&prefix)
+		.ignore_err()
+		.map(|(key, value)| {
+			let stored_at = value.get(..8).map_or(0, utils::bytes::u64_from_bytes_or_zero);
+			(stored_at, key.to_vec())
+		})
+		.collect()
+		.await;
+
+	if stored.len() > SERVER_TXNID_LIMIT {
+		stored.sort_unstable_by_key(|(stored_at, _)| *stored_at);
+		let expired = stored.len().saturating_sub(SERVER_TXNID_LIMIT);
+		for (_, key) in stored.drain(..expired) {
+			self.db.servertxnid_response.remove(&key);
+		}
+	}
+}
+
+/// The stored response to a federation transaction. If there's no entry, this
+/// is a new federation transaction.
+#[implement(Service)]
+pub async fn existing_server_txnid(
+	&self,
+	origin: &ServerName,
+	txn_id: &TransactionId,
+) -> Result<Vec<u8>> {
+	let key = (origin, txn_id);
+	let value = self.db.servertxnid_response.qry(&key).await?;
+	value
+		.get(8..)
+		.map(<[u8]>::to_vec)
+		.ok_or_else(|| err!(Database("Invalid response in server txnid data.")))
+}
+
+/// Key of a federation transaction in `mutex_server_txnid`. Server names
+/// contain no spaces, so the key is unique to the pair.
+#[must_use]
+pub fn server_txnid_key(origin: &ServerName, txn_id: &TransactionId) -> String {
+	format!("{origin} {txn_id}")
+}
@@ -1,3 +1,6 @@
+mod recaptcha;
+mod registration_tokens;
+
 use std::{
 	collections::{BTreeMap, HashSet},
 	sync::{Arc, RwLock},
@@ -5,37 +8,46 @@
 
 use conduwuit::{
 	err, error, implement, utils,
-	utils::{hash, string::EMPTY},
+	utils::{hash, string::EMPTY, MutexMap},
 	Error, Result,
 };
 use database::{Deserialized, Json, Map};
 use ruma::{
 	api::client::{
 		error::ErrorKind,
-		uiaa::{AuthData, AuthType, Password, UiaaInfo, UserIdentifier},
+		uiaa::{AuthData, AuthType, EmailIdentity, Password, UiaaInfo, UserIdentifier},
 	},
 	CanonicalJsonValue, DeviceId, OwnedDeviceId, OwnedUserId, UserId,
 };
 
-use crate::{config, globals, users, Dep};
+pub use self::registration_tokens::RegistrationTokenInfo;
+use crate::{client, config, email, globals, users, Dep};
 
 pub struct Service {
 	userdevicesessionid_uiaarequest: RwLock<RequestMap>,
+	userdevicesessionid_email: RwLock<EmailMap>,
+	userdevicesessionid_registrationtoken: RwLock<TokenMap>,
+	registration_token_mutex: MutexMap<String, ()>,
 	db: Data,
 	services: Services,
 }
 
 struct Services {
+	client: Dep<client::Service>,
+	email: Dep<email::Service>,
 	globals: Dep<globals::Service>,
 	users: Dep<users::Service>,
 	config: Dep<config::Service>,
 }
 
 struct Data {
+	registrationtoken_info: Arc<Map>,
 	userdevicesessionid_uiaainfo: Arc<Map>,
 }
 
 type RequestMap = BTreeMap<RequestKey, CanonicalJsonValue>;
+type EmailMap = BTreeMap<RequestKey, String>;
+type TokenMap = BTreeMap<RequestKey, String>;
 type RequestKey = (OwnedUserId, OwnedDeviceId, String);
 
 pub const SESSION_ID_LENGTH: usize = 32;
@@ -44,10 +56,16 @@ impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			userdevicesessionid_uiaarequest: RwLock::new(RequestMap::new()),
+			userdevicesessionid_email: RwLock::new(EmailMap::new()),
+			userdevicesessionid_registrationtoken: RwLock::new(TokenMap::new()),
+			registration_token_mutex: MutexMap::new(),
 			db: Data {
+				registrationtoken_info: args.db["registrationtoken_info"].clone(),
 				userdevicesessionid_uiaainfo: args.db["userdevicesessionid_uiaainfo"].clone(),
 			},
 			services: Services {
+				client: args.depend::<client::Service>("client"),
+				email: args.depend::<email::Service>("email"),
 				globals: args.depend::<globals::Service>("globals"),
 				users: args.depend::<users::Service>("users"),
 				config: args.depend::<config::Service>("config"),
@@ -152,30 +170,45 @@ pub async fn try_auth(
 				));
 			};
 
-			let user_id = UserId::parse_with_server_name(
+			let auth_user_id = UserId::parse_with_server_name(
 				username.clone(),
 				self.services.globals.server_name(),
 			)
 			.map_err(|_| Error::BadRequest(ErrorKind::InvalidParam, "User ID is invalid."))?;
 
+			// The password must be that of the user the session belongs to
+			let hash = if auth_user_id == user_id {
+				self.services.users.password_hash(user_id).await.ok()
+			} else {
+				None
+			};
+
 			// Check if password is correct
-			if let Ok(hash) = self.services.users.password_hash(&user_id).await {
-				let hash_matches = hash::verify_password(password, &hash).is_ok();
-				if !hash_matches {
-					uiaainfo.auth_error = Some(ruma::api::client::error::StandardErrorBody {
-						kind: ErrorKind::forbidden(),
-						message: "Invalid username or password.".to_owned(),
-					});
-					return Ok((false, uiaainfo));
-				}
+			if !hash.is_some_and(|hash| hash::verify_password(password, &hash).is_ok()) {
+				uiaainfo.auth_error = Some(ruma::api::client::error::StandardErrorBody {
+					kind: ErrorKind::forbidden(),
+					message: "Invalid username or password.".to_owned(),
+				});
+				return Ok((false, uiaainfo));
 			}
 
 			// Password was correct! Let's add it to `completed`
 			uiaainfo.completed.push(AuthType::Password);
 		},
 		| AuthData::RegistrationToken(t) => {
-			let tokens = self.read_tokens().await?;
-			if tokens.contains(t.token.trim()) {
+			let token = t.token.trim();
+			if self.is_valid_registration_token(token).await {
+				// Counted by the route once the account was created
+				let key = (
+					user_id.to_owned(),
+					device_id.to_owned(),
+					uiaainfo.session.clone().expect("session is always set"),
+				);
+				self.userdevicesessionid_registrationtoken
+					.write()
+					.expect("locked for writing")
+					.insert(key, token.to_owned());
+
 				uiaainfo.completed.push(AuthType::RegistrationToken);
 			} else {
 				uiaainfo.auth_error = Some(ruma::api::client::error::StandardErrorBody {
@@ -185,6 +218,42 @@ pub async fn try_auth(
 				return Ok((false, uiaainfo));
 			}
 		},
+		| AuthData::ReCaptcha(r) => {
+			if self.verify_recaptcha(&r.response).await? {
+				uiaainfo.completed.push(AuthType::ReCaptcha);
+			} else {
+				uiaainfo.auth_error = Some(ruma::api::client::error::StandardErrorBody {
+					kind: ErrorKind::forbidden(),
+					message: "reCAPTCHA verification failed.".to_owned(),
+				});
+				return Ok((false, uiaainfo));
+			}
+		},
+		| AuthData::EmailIdentity(EmailIdentity { thirdparty_id_creds, .. }) => {
+			let Some(email) = self.services.email.validated_email(
+				thirdparty_id_creds.sid.as_str(),
+				thirdparty_id_creds.client_secret.as_str(),
+			) else {
+				uiaainfo.auth_error = Some(ruma::api::client::error::StandardErrorBody {
+					kind: ErrorKind::ThreepidAuthFailed,
+					message: "Email address has not been validated.".to_owned(),
+				});
+				return Ok((false, uiaainfo));
+			};
+
+			// Remembered for the route to act on once the whole flow completed
+			let key = (
+				user_id.to_owned(),
+				device_id.to_owned(),
+				uiaainfo.session.clone().expect("session is always set"),
+			);
+			self.userdevicesessionid_email
+				.write()
+				.expect("locked for writing")
+				.insert(key, email);
+
+			uiaainfo.completed.push(AuthType::EmailIdentity);
+		},
 		| AuthData::Dummy(_) => {
 			uiaainfo.completed.push(AuthType::Dummy);
 		},
@@ -260,6 +329,38 @@ pub fn get_uiaa_request(
 		.cloned()
 }
 
+/// The email address validated through the `m.login.email.identity` stage of
+/// a session. Taken out once the route acted on it.
+#[implement(Service)]
+pub fn take_validated_email(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+	session: &str,
+) -> Option<String> {
+	let key = (user_id.to_owned(), device_id.to_owned(), session.to_owned());
+	self.userdevicesessionid_email
+		.write()
+		.expect("locked for writing")
+		.remove(&key)
+}
+
+/// The registration token accepted by the `m.login.registration_token` stage
+/// of a session. Taken out once the route acted on it.
+#[implement(Service)]
+pub fn take_registration_token(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+	session: &str,
+) -> Option<String> {
+	let key = (user_id.to_owned(), device_id.to_owned(), session.to_owned());
+	self.userdevicesessionid_registrationtoken
+		.write()
+		.expect("locked for writing")
+		.remove(&key)
+}
+
 #[implement(Service)]
 fn update_uiaa_session(
 	&self,
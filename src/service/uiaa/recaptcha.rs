@@ -0,0 +1,47 @@
+use conduwuit::{debug_warn, err, implement, Result};
+use serde::Deserialize;
+
+const SITEVERIFY_URL: &str = "https://www.google.com/recaptcha/api/siteverify";
+
+#[derive(Deserialize)]
+struct SiteVerifyResponse {
+	success: bool,
+}
+
+/// The public site key clients should use for the reCAPTCHA challenge, if the
+/// stage is enabled.
+#[implement(super::Service)]
+#[must_use]
+pub fn recaptcha_site_key(&self) -> Option<&str> {
+	self.services
+		.config
+		.recaptcha_private_site_key
+		.as_ref()
+		.and(self.services.config.recaptcha_site_key.as_deref())
+}
+
+/// Verifies a client's reCAPTCHA response with Google.
+#[implement(super::Service)]
+pub async fn verify_recaptcha(&self, response: &str) -> Result<bool> {
+	let Some(secret) = self.services.config.recaptcha_private_site_key.as_deref() else {
+		return Ok(false);
+	};
+
+	let body = self
+		.services
+		.client
+		.default
+		.post(SITEVERIFY_URL)
+		.form(&[("secret", secret), ("response", response)])
+		.send()
+		.await?
+		.error_for_status()?
+		.bytes()
+		.await?;
+
+	let result: SiteVerifyResponse = serde_json::from_slice(&body).map_err(|e| {
+		err!(BadServerResponse(debug_warn!("Invalid reCAPTCHA verification response: {e}")))
+	})?;
+
+	Ok(result.success)
+}
@@ -0,0 +1,140 @@
+use conduwuit::{
+	implement, utils,
+	utils::{stream::TryIgnore, MutexMapGuard},
+	Err, Result,
+};
+use database::{Deserialized, Json};
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+
+/// Length of randomly generated registration tokens.
+pub const REGISTRATION_TOKEN_LENGTH: usize = 16;
+
+/// A registration token stored in the database (MSC3231).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct RegistrationTokenInfo {
+	/// Number of registrations the token may be used for, unlimited if `None`.
+	pub uses_allowed: Option<u64>,
+
+	/// Number of registrations which have completed using the token.
+	pub completed: u64,
+
+	/// Time in milliseconds since the unix epoch after which the token is no
+	/// longer valid, never expiring if `None`.
+	pub expiry_time: Option<u64>,
+}
+
+impl RegistrationTokenInfo {
+	/// Whether the token can currently be used to register.
+	#[must_use]
+	pub fn is_valid(&self) -> bool {
+		let exhausted = self
+			.uses_allowed
+			.is_some_and(|uses_allowed| self.completed >= uses_allowed);
+
+		let expired = self
+			.expiry_time
+			.is_some_and(|expiry_time| utils::millis_since_unix_epoch() >= expiry_time);
+
+		!exhausted && !expired
+	}
+}
+
+/// Whether registration requires a token, either from the config or from the
+/// database.
+#[implement(super::Service)]
+pub async fn registration_token_required(&self) -> bool {
+	self.services.globals.registration_token.is_some()
+		|| self.db.registrationtoken_info.count().await > 0
+}
+
+/// Checks whether the token can currently be used to register.
+#[implement(super::Service)]
+pub async fn is_valid_registration_token(&self, token: &str) -> bool {
+	if self
+		.read_tokens()
+		.await
+		.is_ok_and(|tokens| tokens.contains(token))
+	{
+		return true;
+	}
+
+	self.get_registration_token(token)
+		.await
+		.is_ok_and(|info| info.is_valid())
+}
+
+/// Locks a registration token until the guard is dropped. Registrations hold
+/// it from checking the token until its use was counted, so concurrent
+/// registrations can't use a token beyond its limit.
+#[implement(super::Service)]
+pub async fn lock_registration_token(&self, token: &str) -> MutexMapGuard<String, ()> {
+	self.registration_token_mutex.lock(token).await
+}
+
+/// Records a completed registration against a token from the database, once
+/// the account was created. Tokens from the config have no usage limit and
+/// are left alone. The caller must hold the token's lock.
+#[implement(super::Service)]
+pub async fn use_registration_token(&self, token: &str) {
+	if let Ok(mut info) = self.get_registration_token(token).await {
+		info.completed = info.completed.saturating_add(1);
+		self.db.registrationtoken_info.raw_put(token, Json(info));
+	}
+}
+
+/// Creates a new registration token, generating a random one if none is
+/// given.
+#[implement(super::Service)]
+pub async fn issue_registration_token(
+	&self,
+	token: Option<String>,
+	uses_allowed: Option<u64>,
+	expiry_time: Option<u64>,
+) -> Result<String> {
+	let token = token.unwrap_or_else(|| utils::random_string(REGISTRATION_TOKEN_LENGTH));
+
+	if token.is_empty()
+		|| !token
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '~' | '-'))
+	{
+		return Err!(Request(InvalidParam(
+			"Registration tokens may only contain the characters [A-Za-z0-9._~-]."
+		)));
+	}
+
+	if self.get_registration_token(&token).await.is_ok() {
+		return Err!(Request(InvalidParam("Registration token already exists.")));
+	}
+
+	let info = RegistrationTokenInfo { uses_allowed, completed: 0, expiry_time };
+	self.db
+		.registrationtoken_info
+		.raw_put(&token, Json(info));
+
+	Ok(token)
+}
+
+#[implement(super::Service)]
+pub async fn revoke_registration_token(&self, token: &str) -> Result {
+	self.get_registration_token(token).await?;
+	self.db.registrationtoken_info.remove(token);
+
+	Ok(())
+}
+
+#[implement(super::Service)]
+pub async fn get_registration_token(&self, token: &str) -> Result<RegistrationTokenInfo> {
+	self.db
+		.registrationtoken_info
+		.get(token)
+		.await
+		.deserialized()
+}
+
+/// Returns all registration tokens stored in the database.
+#[implement(super::Service)]
+pub fn registration_tokens(&self) -> impl Stream<Item = (&str, RegistrationTokenInfo)> + Send {
+	self.db.registrationtoken_info.stream().ignore_err()
+}
@@ -0,0 +1,89 @@
+use conduwuit::{implement, Err, Result};
+use database::{Deserialized, Json};
+use ruma::{
+	api::client::dehydrated_device::{
+		put_dehydrated_device::unstable::Request, DehydratedDeviceData,
+	},
+	serde::Raw,
+	DeviceId, OwnedDeviceId, UserId,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DehydratedDevice {
+	/// Unique ID of the device.
+	pub device_id: OwnedDeviceId,
+
+	/// Contains serialized and encrypted private data.
+	pub device_data: Raw<DehydratedDeviceData>,
+}
+
+/// Creates or replaces the user's dehydrated device (MSC3814), along with its
+/// device keys and one-time keys.
+#[implement(super::Service)]
+pub async fn set_dehydrated_device(&self, user_id: &UserId, request: Request) -> Result {
+	self.remove_dehydrated_device(user_id).await.ok();
+
+	if self
+		.get_device_metadata(user_id, &request.device_id)
+		.await
+		.is_ok()
+	{
+		return Err!(Request(InvalidParam("Device ID is already in use.")));
+	}
+
+	self.create_device(
+		user_id,
+		&request.device_id,
+		None,
+		request.initial_device_display_name.clone(),
+		None,
+	)
+	.await?;
+
+	self.add_device_keys(user_id, &request.device_id, &request.device_keys)
+		.await;
+
+	for (key_id, one_time_key) in &request.one_time_keys {
+		self.add_one_time_key(user_id, &request.device_id, key_id, one_time_key)
+			.await?;
+	}
+
+	let device = DehydratedDevice {
+		device_id: request.device_id,
+		device_data: request.device_data,
+	};
+
+	self.db
+		.userid_dehydrateddevice
+		.raw_put(user_id, Json(device));
+
+	Ok(())
+}
+
+/// Removes the user's dehydrated device, returning its device ID.
+#[implement(super::Service)]
+pub async fn remove_dehydrated_device(&self, user_id: &UserId) -> Result<OwnedDeviceId> {
+	let device = self.get_dehydrated_device(user_id).await?;
+
+	self.db.userid_dehydrateddevice.remove(user_id);
+	self.remove_device(user_id, &device.device_id).await;
+
+	Ok(device.device_id)
+}
+
+#[implement(super::Service)]
+pub async fn get_dehydrated_device(&self, user_id: &UserId) -> Result<DehydratedDevice> {
+	self.db
+		.userid_dehydrateddevice
+		.get(user_id)
+		.await
+		.deserialized()
+}
+
+#[implement(super::Service)]
+pub async fn is_dehydrated_device(&self, user_id: &UserId, device_id: &DeviceId) -> bool {
+	self.get_dehydrated_device(user_id)
+		.await
+		.is_ok_and(|device| device.device_id == device_id)
+}
@@ -1,3 +1,6 @@
+mod dehydrated_device;
+mod refresh_token;
+
 use std::{collections::BTreeMap, mem, sync::Arc};
 
 use conduwuit::{
@@ -19,6 +22,7 @@
 };
 use serde_json::json;
 
+pub use self::dehydrated_device::DehydratedDevice;
 use crate::{account_data, admin, globals, rooms, Dep};
 
 pub struct Service {
@@ -41,13 +45,17 @@ struct Data {
 	onetimekeyid_onetimekeys: Arc<Map>,
 	openidtoken_expiresatuserid: Arc<Map>,
 	logintoken_expiresatuserid: Arc<Map>,
+	refreshtoken_userdeviceid: Arc<Map>,
 	todeviceid_events: Arc<Map>,
+	token_expiresat: Arc<Map>,
 	token_userdeviceid: Arc<Map>,
 	userdeviceid_metadata: Arc<Map>,
+	userdeviceid_refreshtoken: Arc<Map>,
 	userdeviceid_token: Arc<Map>,
 	userfilterid_filter: Arc<Map>,
 	userid_avatarurl: Arc<Map>,
 	userid_blurhash: Arc<Map>,
+	userid_dehydrateddevice: Arc<Map>,
 	userid_devicelistversion: Arc<Map>,
 	userid_displayname: Arc<Map>,
 	userid_lastonetimekeyupdate: Arc<Map>,
@@ -76,13 +84,17 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				onetimekeyid_onetimekeys: args.db["onetimekeyid_onetimekeys"].clone(),
 				openidtoken_expiresatuserid: args.db["openidtoken_expiresatuserid"].clone(),
 				logintoken_expiresatuserid: args.db["logintoken_expiresatuserid"].clone(),
+				refreshtoken_userdeviceid: args.db["refreshtoken_userdeviceid"].clone(),
 				todeviceid_events: args.db["todeviceid_events"].clone(),
+				token_expiresat: args.db["token_expiresat"].clone(),
 				token_userdeviceid: args.db["token_userdeviceid"].clone(),
 				userdeviceid_metadata: args.db["userdeviceid_metadata"].clone(),
+				userdeviceid_refreshtoken: args.db["userdeviceid_refreshtoken"].clone(),
 				userdeviceid_token: args.db["userdeviceid_token"].clone(),
 				userfilterid_filter: args.db["userfilterid_filter"].clone(),
 				userid_avatarurl: args.db["userid_avatarurl"].clone(),
 				userid_blurhash: args.db["userid_blurhash"].clone(),
+				userid_dehydrateddevice: args.db["userid_dehydrateddevice"].clone(),
 				userid_devicelistversion: args.db["userid_devicelistversion"].clone(),
 				userid_displayname: args.db["userid_displayname"].clone(),
 				userid_lastonetimekeyupdate: args.db["userid_lastonetimekeyupdate"].clone(),
@@ -272,7 +284,7 @@ pub async fn create_device(
 		&self,
 		user_id: &UserId,
 		device_id: &DeviceId,
-		token: &str,
+		token: Option<&str>,
 		initial_device_display_name: Option<String>,
 		client_ip: Option<String>,
 	) -> Result<()> {
@@ -294,7 +306,12 @@ pub async fn create_device(
 
 		increment(&self.db.userid_devicelistversion, user_id.as_bytes());
 		self.db.userdeviceid_metadata.put(key, Json(val));
-		self.set_token(user_id, device_id, token).await
+
+		if let Some(token) = token {
+			self.set_token(user_id, device_id, token).await?;
+		}
+
+		Ok(())
 	}
 
 	/// Removes a device from a user.
@@ -305,8 +322,11 @@ pub async fn remove_device(&self, user_id: &UserId, device_id: &DeviceId) {
 		if let Ok(old_token) = self.db.userdeviceid_token.qry(&userdeviceid).await {
 			self.db.userdeviceid_token.del(userdeviceid);
 			self.db.token_userdeviceid.remove(&old_token);
+			self.db.token_expiresat.remove(&old_token);
 		}
 
+		self.remove_refresh_token(user_id, device_id).await;
+
 		// Remove todevice events
 		let prefix = (user_id, device_id, Interfix);
 		self.db
@@ -316,7 +336,19 @@ pub async fn remove_device(&self, user_id: &UserId, device_id: &DeviceId) {
 			.ready_for_each(|key| self.db.todeviceid_events.remove(key))
 			.await;
 
-		// TODO: Remove onetimekeys
+		// Remove one-time keys and device keys
+		self.db
+			.onetimekeyid_onetimekeys
+			.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| self.db.onetimekeyid_onetimekeys.remove(key))
+			.await;
+
+		self.db.keyid_key.del(userdeviceid);
+
+		if self.is_dehydrated_device(user_id, device_id).await {
+			self.db.userid_dehydrateddevice.remove(user_id);
+		}
 
 		increment(&self.db.userid_devicelistversion, user_id.as_bytes());
 
@@ -362,9 +394,13 @@ pub async fn set_token(
 		// Remove old token
 		if let Ok(old_token) = self.db.userdeviceid_token.qry(&key).await {
 			self.db.token_userdeviceid.remove(&old_token);
+			self.db.token_expiresat.remove(&old_token);
 			// It will be removed from userdeviceid_token by the insert later
 		}
 
+		// A refresh token is only valid until the device's access token is replaced
+		self.remove_refresh_token(user_id, device_id).await;
+
 		// Assign token to user device combination
 		self.db.userdeviceid_token.put_raw(key, token);
 		self.db.token_userdeviceid.raw_put(token, key);
@@ -514,7 +550,7 @@ pub async fn add_device_keys(
 	pub async fn add_cross_signing_keys(
 		&self,
 		user_id: &UserId,
-		master_key: &Raw<CrossSigningKey>,
+		master_key: Option<&Raw<CrossSigningKey>>,
 		self_signing_key: &Option<Raw<CrossSigningKey>>,
 		user_signing_key: &Option<Raw<CrossSigningKey>>,
 		notify: bool,
@@ -523,15 +559,18 @@ pub async fn add_cross_signing_keys(
 		let mut prefix = user_id.as_bytes().to_vec();
 		prefix.push(0xFF);
 
-		let (master_key_key, _) = parse_master_key(user_id, master_key)?;
+		// Master key
+		if let Some(master_key) = master_key {
+			let (master_key_key, _) = parse_master_key(user_id, master_key)?;
 
-		self.db
-			.keyid_key
-			.insert(&master_key_key, master_key.json().get().as_bytes());
+			self.db
+				.keyid_key
+				.insert(&master_key_key, master_key.json().get().as_bytes());
 
-		self.db
-			.userid_masterkeyid
-			.insert(user_id.as_bytes(), &master_key_key);
+			self.db
+				.userid_masterkeyid
+				.insert(user_id.as_bytes(), &master_key_key);
+		}
 
 		// Self-signing key
 		if let Some(self_signing_key) = self_signing_key {
@@ -921,7 +960,10 @@ pub fn create_openid_token(&self, user_id: &UserId, token: &str) -> Result<u64>
 	/// Find out which user an OpenID access token belongs to.
 	pub async fn find_from_openid_token(&self, token: &str) -> Result<OwnedUserId> {
 		let Ok(value) = self.db.openidtoken_expiresatuserid.get(token).await else {
-			return Err!(Request(Unauthorized("OpenID token is unrecognised")));
+			return Err(Error::BadRequest(
+				ErrorKind::UnknownToken { soft_logout: false },
+				"OpenID token is unrecognised.",
+			));
 		};
 
 		let (expires_at_bytes, user_bytes) = value.split_at(0_u64.to_be_bytes().len());
@@ -934,7 +976,10 @@ pub async fn find_from_openid_token(&self, token: &str) -> Result<OwnedUserId> {
 			debug_warn!("OpenID token is expired, removing");
 			self.db.openidtoken_expiresatuserid.remove(token.as_bytes());
 
-			return Err!(Request(Unauthorized("OpenID token is expired")));
+			return Err(Error::BadRequest(
+				ErrorKind::UnknownToken { soft_logout: false },
+				"OpenID token is expired.",
+			));
 		}
 
 		let user_string = utils::string_from_bytes(user_bytes)
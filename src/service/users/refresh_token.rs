@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use conduwuit::{implement, utils, Error, Result};
+use database::Deserialized;
+use ruma::{api::client::error::ErrorKind, DeviceId, OwnedDeviceId, OwnedUserId, UserId};
+
+/// Assigns a refresh token to the device and makes its current access token
+/// expire after `access_token_ttl`. Returns how long the access token is
+/// valid for.
+#[implement(super::Service)]
+pub async fn set_refresh_token(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+	access_token: &str,
+	refresh_token: &str,
+) -> Duration {
+	use std::num::Saturating as Sat;
+
+	self.remove_refresh_token(user_id, device_id).await;
+
+	let key = (user_id, device_id);
+	self.db
+		.userdeviceid_refreshtoken
+		.put_raw(key, refresh_token);
+	self.db
+		.refreshtoken_userdeviceid
+		.raw_put(refresh_token, key);
+
+	let expires_in = self.services.server.config.access_token_ttl;
+	let expires_at = Sat(utils::millis_since_unix_epoch()) + Sat(expires_in) * Sat(1000);
+	self.db
+		.token_expiresat
+		.raw_put(access_token, expires_at.0);
+
+	Duration::from_secs(expires_in)
+}
+
+/// Find out which device a refresh token belongs to.
+#[implement(super::Service)]
+pub async fn find_from_refresh_token(
+	&self,
+	refresh_token: &str,
+) -> Result<(OwnedUserId, OwnedDeviceId)> {
+	self.db
+		.refreshtoken_userdeviceid
+		.get(refresh_token)
+		.await
+		.deserialized()
+		.map_err(|_| {
+			Error::BadRequest(
+				ErrorKind::UnknownToken { soft_logout: false },
+				"Unknown refresh token.",
+			)
+		})
+}
+
+/// Whether an access token issued alongside a refresh token has expired.
+#[implement(super::Service)]
+pub async fn is_token_expired(&self, access_token: &str) -> bool {
+	self.db
+		.token_expiresat
+		.get(access_token)
+		.await
+		.deserialized()
+		.is_ok_and(|expires_at: u64| expires_at < utils::millis_since_unix_epoch())
+}
+
+#[implement(super::Service)]
+pub(super) async fn remove_refresh_token(&self, user_id: &UserId, device_id: &DeviceId) {
+	let key = (user_id, device_id);
+	if let Ok(old_token) = self.db.userdeviceid_refreshtoken.qry(&key).await {
+		self.db.userdeviceid_refreshtoken.del(key);
+		self.db.refreshtoken_userdeviceid.remove(&old_token);
+	}
+}